@@ -0,0 +1,66 @@
+//! The alt_bn128 curve operations backing the `crypto.bn128*` host functions, using the encodings
+//! of the Eth1 precompiles (EIP-196 and EIP-197).
+//!
+//! G1 points are encoded as the big-endian coordinates x and y, G2 points as the coordinates x and y
+//! in Fq2, each encoded as its imaginary part followed by its real part. The point at infinity is
+//! encoded as all zeroes. Operations return `None` for points which are not on the curve.
+
+use substrate_bn::{pairing_batch, AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+
+pub const G1_SIZE: usize = 64;
+pub const SCALAR_SIZE: usize = 32;
+pub const PAIR_SIZE: usize = 192;
+
+fn fq(raw: &[u8]) -> Option<Fq> {
+    Fq::from_slice(raw).ok()
+}
+
+fn g1(raw: &[u8]) -> Option<G1> {
+    let x = fq(&raw[0..32])?;
+    let y = fq(&raw[32..64])?;
+    if x.is_zero() && y.is_zero() {
+        Some(G1::zero())
+    } else {
+        AffineG1::new(x, y).ok().map(Into::into)
+    }
+}
+
+fn g2(raw: &[u8]) -> Option<G2> {
+    let x = Fq2::new(fq(&raw[32..64])?, fq(&raw[0..32])?);
+    let y = Fq2::new(fq(&raw[96..128])?, fq(&raw[64..96])?);
+    if x.is_zero() && y.is_zero() {
+        Some(G2::zero())
+    } else {
+        AffineG2::new(x, y).ok().map(Into::into)
+    }
+}
+
+fn encode_g1(point: G1) -> [u8; G1_SIZE] {
+    let mut raw = [0u8; G1_SIZE];
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        // Writing into correctly sized slices cannot fail.
+        point.x().to_big_endian(&mut raw[0..32]).unwrap();
+        point.y().to_big_endian(&mut raw[32..64]).unwrap();
+    }
+    raw
+}
+
+/// Adds the G1 points `a` and `b`.
+pub fn add(a: &[u8], b: &[u8]) -> Option<[u8; G1_SIZE]> {
+    Some(encode_g1(g1(a)? + g1(b)?))
+}
+
+/// Multiplies the G1 point by the big-endian scalar, which does not need to be reduced.
+pub fn mul(point: &[u8], scalar: &[u8]) -> Option<[u8; G1_SIZE]> {
+    let scalar = Fr::from_slice(scalar).ok()?;
+    Some(encode_g1(g1(point)? * scalar))
+}
+
+/// Checks whether the product of the pairings of the encoded (G1, G2) pairs is one.
+pub fn pairing_check(pairs: &[u8]) -> Option<bool> {
+    let pairs = pairs
+        .chunks(PAIR_SIZE)
+        .map(|pair| Some((g1(&pair[0..G1_SIZE])?, g2(&pair[G1_SIZE..])?)))
+        .collect::<Option<Vec<_>>>()?;
+    Some(pairing_batch(&pairs) == Gt::one())
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+use wasmi::{Trap, TrapKind};
+
+/// Errors raised by host functions. These abort the execution of the script with a
+/// `TrapKind::Host` trap and are turned back into a `ScoutError` once execution has stopped.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostError {
+    /// The script has used up its tick budget.
+    OutOfTicks,
+    /// A host function could not complete the call.
+    Failure { function: String, message: String },
+}
+
+impl HostError {
+    pub fn failure(function: &str, message: impl Into<String>) -> Self {
+        HostError::Failure {
+            function: function.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for HostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostError::OutOfTicks => write!(f, "Out of ticks"),
+            HostError::Failure { function, message } => {
+                write!(f, "Host function {} failed: {}", function, message)
+            }
+        }
+    }
+}
+
+impl wasmi::HostError for HostError {}
+
+#[derive(Debug)]
+pub enum ScoutError {
+    /// The wasm binary could not be decoded.
+    Decode(String),
+    /// The wasm module failed validation.
+    Validation(String),
+    /// The module could not be instantiated, e.g. due to missing imports or exports.
+    Link(String),
+    /// Execution trapped inside the wasm code.
+    Trap(Trap),
+    /// Execution has run out of ticks.
+    OutOfTicks,
+    /// A host function failed, aborting execution.
+    HostCall {
+        function: String,
+        message: String,
+    },
+    /// The test input is malformed.
+    InvalidInput(String),
+    Io(std::io::Error),
+    Hex(rustc_hex::FromHexError),
+    Yaml(serde_yaml::Error),
+    Json(serde_json::Error),
+}
+
+impl ScoutError {
+    /// Whether the error was caused by the executed block, i.e. the script trapped, ran out of
+    /// ticks or made a failing host call, rather than by the runner or
+    /// its input.
+    pub fn is_block_failure(&self) -> bool {
+        matches!(
+            self,
+            ScoutError::Trap(_) | ScoutError::OutOfTicks | ScoutError::HostCall { .. }
+        )
+    }
+}
+
+impl fmt::Display for ScoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoutError::Decode(message) => write!(f, "Decoding failed: {}", message),
+            ScoutError::Validation(message) => write!(f, "Validation failed: {}", message),
+            ScoutError::Link(message) => write!(f, "Linking failed: {}", message),
+            ScoutError::Trap(trap) => write!(f, "Trapped: {:?}", trap.kind()),
+            ScoutError::OutOfTicks => write!(f, "Out of ticks"),
+            ScoutError::HostCall { function, message } => {
+                write!(f, "Host function {} failed: {}", function, message)
+            }
+            ScoutError::InvalidInput(message) => write!(f, "Invalid input: {}", message),
+            ScoutError::Io(error) => write!(f, "I/O error: {}", error),
+            ScoutError::Hex(error) => write!(f, "Hex error: {}", error),
+            ScoutError::Yaml(error) => write!(f, "YAML error: {}", error),
+            ScoutError::Json(error) => write!(f, "JSON error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ScoutError {}
+
+impl From<HostError> for ScoutError {
+    fn from(error: HostError) -> Self {
+        match error {
+            HostError::OutOfTicks => ScoutError::OutOfTicks,
+            HostError::Failure { function, message } => ScoutError::HostCall { function, message },
+        }
+    }
+}
+
+impl From<std::io::Error> for ScoutError {
+    fn from(error: std::io::Error) -> Self {
+        ScoutError::Io(error)
+    }
+}
+
+impl From<rustc_hex::FromHexError> for ScoutError {
+    fn from(error: rustc_hex::FromHexError) -> Self {
+        ScoutError::Hex(error)
+    }
+}
+
+impl From<serde_yaml::Error> for ScoutError {
+    fn from(error: serde_yaml::Error) -> Self {
+        ScoutError::Yaml(error)
+    }
+}
+
+impl From<serde_json::Error> for ScoutError {
+    fn from(error: serde_json::Error) -> Self {
+        ScoutError::Json(error)
+    }
+}
+
+impl From<parity_wasm::elements::Error> for ScoutError {
+    fn from(error: parity_wasm::elements::Error) -> Self {
+        ScoutError::Decode(error.to_string())
+    }
+}
+
+impl From<wasmi::Error> for ScoutError {
+    fn from(error: wasmi::Error) -> Self {
+        match error {
+            wasmi::Error::Validation(message) => ScoutError::Validation(message),
+            wasmi::Error::Trap(trap) => trap.into(),
+            wasmi::Error::Host(host_error) => match host_error.downcast_ref::<HostError>() {
+                Some(host_error) => host_error.clone().into(),
+                None => ScoutError::HostCall {
+                    function: "unknown".to_string(),
+                    message: host_error.to_string(),
+                },
+            },
+            other => ScoutError::Link(other.to_string()),
+        }
+    }
+}
+
+impl From<Trap> for ScoutError {
+    fn from(trap: Trap) -> Self {
+        match trap.kind() {
+            TrapKind::Host(host_error) => match host_error.downcast_ref::<HostError>() {
+                Some(host_error) => host_error.clone().into(),
+                None => ScoutError::Trap(trap),
+            },
+            _ => ScoutError::Trap(trap),
+        }
+    }
+}
@@ -0,0 +1,2227 @@
+//! Scout is an Eth 2.0 Phase 2 execution prototyping engine.
+//!
+//! The library exposes the engine itself: a [`BeaconState`] holding the execution scripts, a
+//! [`ShardState`] holding the state root of each execution environment, and
+//! [`process_shard_block`] which executes a [`ShardBlock`] against them. The YAML test runner
+//! used by the `phase2-scout` binary is available as [`process_yaml_test`].
+
+#[macro_use]
+extern crate log;
+
+use primitive_types::{U256, U512};
+use rustc_hex::ToHex;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use wasmi::memory_units::Bytes;
+use wasmi::{
+    Error as InterpreterError, Externals, FuncInstance, FuncRef, ImportsBuilder, MemoryRef, Module,
+    ModuleImportResolver, ModuleInstance, RuntimeArgs, RuntimeValue, Signature, Trap, ValueType,
+};
+
+mod bn128;
+mod error;
+mod metering;
+pub mod montgomery;
+pub mod output;
+pub mod report;
+pub mod runner;
+mod ssz;
+pub mod testfile;
+pub mod types;
+
+pub use crate::error::{HostError, ScoutError};
+pub use crate::metering::{AggregateCost, FieldCost, HashCost, MerkleCost, PairingCost, TickCosts};
+use crate::montgomery::Modulus;
+pub use crate::report::TestReport;
+pub use crate::testfile::process_yaml_test;
+use crate::types::*;
+use blst::min_pk as bls;
+use blst::BLST_ERROR;
+
+const LOADPRESTATEROOT_FUNC_INDEX: usize = 0;
+const BLOCKDATASIZE_FUNC_INDEX: usize = 1;
+const BLOCKDATACOPY_FUNC_INDEX: usize = 2;
+const SAVEPOSTSTATEROOT_FUNC_INDEX: usize = 3;
+const PUSHNEWDEPOSIT_FUNC_INDEX: usize = 4;
+const USETICKS_FUNC_INDEX: usize = 5;
+const DEBUG_PRINT32_FUNC: usize = 6;
+const DEBUG_PRINT64_FUNC: usize = 7;
+const DEBUG_PRINTMEM_FUNC: usize = 8;
+const DEBUG_PRINTMEMHEX_FUNC: usize = 9;
+const BIGNUM_ADD256_FUNC: usize = 10;
+const BIGNUM_SUB256_FUNC: usize = 11;
+const BIGNUM_MUL256_FUNC: usize = 12;
+const BIGNUM_DIV256_FUNC: usize = 13;
+const BIGNUM_MOD256_FUNC: usize = 14;
+const BIGNUM_ADDMOD256_FUNC: usize = 15;
+const BIGNUM_MULMOD256_FUNC: usize = 16;
+const BIGNUM_LT256_FUNC: usize = 17;
+const BIGNUM_GT256_FUNC: usize = 18;
+const BIGNUM_EQ256_FUNC: usize = 19;
+const BIGNUM_WRAPPINGADD256_FUNC: usize = 20;
+const BIGNUM_WRAPPINGSUB256_FUNC: usize = 21;
+const BIGNUM_WRAPPINGMUL256_FUNC: usize = 22;
+const BIGNUM_MULMODMONT256_FUNC: usize = 23;
+const BIGNUM_ADDMODMONT256_FUNC: usize = 24;
+const BIGNUM_SUBMODMONT256_FUNC: usize = 25;
+const BIGNUM_MULMODMONT384_FUNC: usize = 26;
+const BIGNUM_ADDMODMONT384_FUNC: usize = 27;
+const BIGNUM_SUBMODMONT384_FUNC: usize = 28;
+const BIGNUM_FIELDMUL_FUNC: usize = 29;
+const BIGNUM_FIELDADD_FUNC: usize = 30;
+const BIGNUM_FIELDSUB_FUNC: usize = 31;
+const BIGNUM_FIELDTOMONT_FUNC: usize = 32;
+const BIGNUM_FIELDFROMMONT_FUNC: usize = 33;
+const CRYPTO_KECCAK256_FUNC: usize = 34;
+const CRYPTO_SHA256_FUNC: usize = 35;
+const CRYPTO_BLSVERIFY_FUNC: usize = 36;
+const CRYPTO_BLSAGGREGATEVERIFY_FUNC: usize = 37;
+const CRYPTO_BLSFASTAGGREGATEVERIFY_FUNC: usize = 38;
+const CRYPTO_ECRECOVER_FUNC: usize = 39;
+const CRYPTO_BN128ADD_FUNC: usize = 40;
+const CRYPTO_BN128MUL_FUNC: usize = 41;
+const CRYPTO_BN128PAIRING_FUNC: usize = 42;
+const SSZ_HASHTREEROOT_FUNC: usize = 43;
+const SSZ_VERIFYMULTIPROOF_FUNC: usize = 44;
+const STORAGELOAD_FUNC_INDEX: usize = 45;
+const STORAGESTORE_FUNC_INDEX: usize = 46;
+
+/// The domain separation tag of the Eth2 BLS signature scheme (proof of possession).
+const BLS_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+const BLS_PUBKEY_SIZE: u32 = 48;
+const BLS_SIGNATURE_SIZE: u32 = 96;
+/// The order of the secp256k1 group.
+const SECP256K1_ORDER: U256 = U256([
+    0xbfd2_5e8c_d036_4141,
+    0xbaae_dce6_af48_a03b,
+    0xffff_ffff_ffff_fffe,
+    0xffff_ffff_ffff_ffff,
+]);
+
+/// Decodes and validates a wasm module, keeping decoding and validation failures apart, and
+/// injects metering charging `costs` into it.
+fn load_metered_module(code: &[u8], costs: &TickCosts) -> Result<Module, ScoutError> {
+    let module: parity_wasm::elements::Module = parity_wasm::elements::deserialize_buffer(code)?;
+    // Validate before instrumenting, the injection expects a well-formed module.
+    Module::from_parity_wasm_module(module.clone())?;
+    let module = metering::inject_metering(module, costs)?;
+    Ok(Module::from_parity_wasm_module(module)?)
+}
+
+/// Instantiates a library with metering charging `costs` injected. Its start function and, once
+/// linked, its exports are run with `runtime` as externals, so they use the script's ticks.
+fn load_import(
+    code: &[u8],
+    costs: &TickCosts,
+    runtime: &mut Runtime,
+) -> Result<wasmi::ModuleRef, ScoutError> {
+    let module = load_metered_module(code, costs)?;
+    let mut imports = ImportsBuilder::new();
+    imports.push_resolver("env", &MeteringImportResolver);
+    let instance = ModuleInstance::new(&module, &imports)?.run_start(runtime)?;
+    Ok(instance)
+}
+
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct Library {
+    pub name: String,
+    pub code: Vec<u8>,
+}
+
+// TODO: move elsehwere?
+pub type DepositBlob = Vec<u8>;
+
+/// The key/value storage of an execution environment. Keys which are not present read as zero, and
+/// storing zero removes a key.
+pub type Storage = BTreeMap<Bytes32, Bytes32>;
+
+/// The number of storage accesses made by a script.
+#[derive(Default, PartialEq, Clone, Copy, Debug)]
+pub struct StorageAccesses {
+    pub loads: u32,
+    pub stores: u32,
+}
+
+/// The tick budget given to a block when nothing else is configured.
+pub const DEFAULT_TICKS: u32 = 10_000_000;
+
+/// The outcome of executing a script via [`execute_code`].
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct ExecutionResult {
+    pub post_state: Bytes32,
+    pub post_storage: Storage,
+    pub deposits: Vec<DepositBlob>,
+    pub ticks_used: u32,
+    pub ticks_left: u32,
+    pub storage_accesses: StorageAccesses,
+}
+
+/// The outcome of processing a shard block via [`process_shard_block`].
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct ShardBlockResult {
+    pub deposit_receipts: Vec<Deposit>,
+    pub ticks_used: u32,
+    pub ticks_left: u32,
+    pub storage_accesses: StorageAccesses,
+}
+
+struct Runtime<'a> {
+    code: &'a [u8],
+    libraries: &'a [Library],
+    costs: &'a TickCosts,
+    ticks_limit: u32,
+    ticks_left: u32,
+    memory: Option<MemoryRef>,
+    pre_state: &'a Bytes32,
+    block_data: &'a ShardBlockBody,
+    post_state: Bytes32,
+    storage: Storage,
+    storage_accesses: StorageAccesses,
+    deposits: Vec<DepositBlob>,
+}
+
+impl<'a> Runtime<'a> {
+    fn new(
+        code: &'a [u8],
+        libraries: &'a [Library],
+        pre_state: &'a Bytes32,
+        storage: &Storage,
+        block_data: &'a ShardBlockBody,
+        ticks: u32,
+        costs: &'a TickCosts,
+    ) -> Runtime<'a> {
+        Runtime {
+            code,
+            libraries,
+            costs,
+            ticks_limit: ticks,
+            ticks_left: ticks,
+            memory: None,
+            pre_state,
+            block_data,
+            post_state: Bytes32::default(),
+            storage: storage.clone(),
+            storage_accesses: StorageAccesses::default(),
+            deposits: Vec::new(),
+        }
+    }
+
+    fn execute(&mut self) -> Result<ExecutionResult, ScoutError> {
+        let module = load_metered_module(self.code, self.costs)?;
+        let mut imports = ImportsBuilder::new();
+        // TODO: remove this and rely on Eth2ImportResolver and DebugImportResolver
+        imports.push_resolver("env", &RuntimeModuleImportResolver);
+        imports.push_resolver("eth2", &Eth2ImportResolver);
+        imports.push_resolver("bignum", &BignumImportResolver);
+        imports.push_resolver("debug", &DebugImportResolver);
+        imports.push_resolver("crypto", &CryptoImportResolver);
+        imports.push_resolver("ssz", &SszImportResolver);
+
+        // Load all libraries
+        // NOTE: creating this variable here to track lifetime
+        let libraries: Result<Vec<(String, wasmi::ModuleRef)>, ScoutError> = self
+            .libraries
+            .iter()
+            .map(|library| {
+                let instance = load_import(&library.code, self.costs, self)?;
+                Ok((library.name.to_string(), instance))
+            })
+            .collect();
+        let libraries = libraries?;
+
+        // Link them to the current instance
+        for library in &libraries {
+            debug!("Attaching library: {}", &library.0);
+            imports.push_resolver(&library.0, &library.1);
+        }
+
+        // The start function is metered too, so it has to run with the runtime as externals.
+        let instance = ModuleInstance::new(&module, &imports)?.run_start(self)?;
+
+        let internal_mem = instance
+            .export_by_name("memory")
+            .ok_or_else(|| ScoutError::Link("Module expected to have 'memory' export".to_string()))?
+            .as_memory()
+            .cloned()
+            .ok_or_else(|| ScoutError::Link("'memory' export should be a memory".to_string()))?;
+
+        self.memory = Some(internal_mem);
+
+        let result = instance.invoke_export("main", &[], self)?;
+
+        info!("Result: {:?}", result);
+
+        // TODO: avoid cloning here
+        Ok(ExecutionResult {
+            post_state: self.post_state,
+            post_storage: self.storage.clone(),
+            deposits: self.deposits.clone(),
+            ticks_used: self.ticks_limit - self.ticks_left,
+            ticks_left: self.ticks_left,
+            storage_accesses: self.storage_accesses,
+        })
+    }
+
+    /// Charges `ticks` against the remaining budget.
+    fn use_ticks(&mut self, ticks: u64) -> Result<(), Trap> {
+        if u64::from(self.ticks_left) < ticks {
+            return Err(HostError::OutOfTicks.into());
+        }
+        self.ticks_left -= ticks as u32;
+        Ok(())
+    }
+
+    /// Returns the memory of the executing module, checking that the `length` bytes at `ptr` are
+    /// within its bounds.
+    fn checked_memory(&self, function: &str, ptr: u32, length: u32) -> Result<&MemoryRef, Trap> {
+        let memory = self
+            .memory
+            .as_ref()
+            .ok_or_else(|| HostError::failure(function, "memory is not available"))?;
+        let memory_size = Bytes::from(memory.current_size()).0 as u64;
+        if u64::from(ptr) + u64::from(length) > memory_size {
+            return Err(HostError::failure(
+                function,
+                format!(
+                    "out of bounds memory access at {} for {} bytes (memory size is {})",
+                    ptr, length, memory_size
+                ),
+            )
+            .into());
+        }
+        Ok(memory)
+    }
+
+    fn memory_get(&self, function: &str, ptr: u32, length: u32) -> Result<Vec<u8>, Trap> {
+        let memory = self.checked_memory(function, ptr, length)?;
+        memory
+            .get(ptr, length as usize)
+            .map_err(|err| HostError::failure(function, err.to_string()).into())
+    }
+
+    fn memory_get_into(&self, function: &str, ptr: u32, buf: &mut [u8]) -> Result<(), Trap> {
+        let memory = self.checked_memory(function, ptr, buf.len() as u32)?;
+        memory
+            .get_into(ptr, buf)
+            .map_err(|err| HostError::failure(function, err.to_string()).into())
+    }
+
+    fn memory_set(&self, function: &str, ptr: u32, value: &[u8]) -> Result<(), Trap> {
+        let memory = self.checked_memory(function, ptr, value.len() as u32)?;
+        memory
+            .set(ptr, value)
+            .map_err(|err| HostError::failure(function, err.to_string()).into())
+    }
+
+    /// Reads a 256-bit big-endian number from memory.
+    fn load_u256(&self, function: &str, ptr: u32) -> Result<U256, Trap> {
+        let mut raw = [0u8; 32];
+        self.memory_get_into(function, ptr, &mut raw)?;
+        Ok(U256::from_big_endian(&raw))
+    }
+
+    /// Reads the two 256-bit operands pointed to by the first two arguments.
+    fn load_u256_operands(&self, function: &str, args: &RuntimeArgs) -> Result<(U256, U256), Trap> {
+        let a = self.load_u256(function, args.nth(0))?;
+        let b = self.load_u256(function, args.nth(1))?;
+        Ok((a, b))
+    }
+
+    /// Writes a 256-bit number to memory in big-endian order.
+    fn store_u256(&self, function: &str, ptr: u32, value: U256) -> Result<(), Trap> {
+        let mut raw = [0u8; 32];
+        value.to_big_endian(&mut raw);
+        self.memory_set(function, ptr, &raw)
+    }
+
+    /// Reads `count` consecutive compressed BLS public keys. Returns `None` if any of them is not
+    /// a valid key.
+    fn load_bls_pubkeys(
+        &self,
+        function: &str,
+        ptr: u32,
+        count: u32,
+    ) -> Result<Option<Vec<bls::PublicKey>>, Trap> {
+        let length = count.checked_mul(BLS_PUBKEY_SIZE).ok_or_else(|| {
+            HostError::failure(function, format!("too many public keys: {}", count))
+        })?;
+        let raw = self.memory_get(function, ptr, length)?;
+        Ok(raw
+            .chunks(BLS_PUBKEY_SIZE as usize)
+            .map(|pubkey| bls::PublicKey::key_validate(pubkey).ok())
+            .collect())
+    }
+
+    /// Reads a compressed BLS signature. Returns `None` if it is not a valid signature.
+    fn load_bls_signature(&self, function: &str, ptr: u32) -> Result<Option<bls::Signature>, Trap> {
+        let raw = self.memory_get(function, ptr, BLS_SIGNATURE_SIZE)?;
+        Ok(bls::Signature::sig_validate(&raw, true).ok())
+    }
+
+    /// Reads `count` consecutive 32-byte chunks.
+    fn load_chunks(&self, function: &str, ptr: u32, count: u32) -> Result<Vec<ssz::Chunk>, Trap> {
+        let length = count
+            .checked_mul(ssz::CHUNK_SIZE as u32)
+            .ok_or_else(|| HostError::failure(function, format!("too many chunks: {}", count)))?;
+        let raw = self.memory_get(function, ptr, length)?;
+        Ok(raw
+            .chunks(ssz::CHUNK_SIZE)
+            .map(|bytes| {
+                let mut chunk = [0u8; ssz::CHUNK_SIZE];
+                chunk.copy_from_slice(bytes);
+                chunk
+            })
+            .collect())
+    }
+
+    /// Applies a binary operation modulo the `size` byte modulus pointed to by the third argument
+    /// to the operands pointed to by the first two, writing the result to the fourth. `cost` is
+    /// charged before the operands are read.
+    fn modulus_op(
+        &mut self,
+        function: &str,
+        size: u32,
+        args: &RuntimeArgs,
+        op: fn(&Modulus, &[u64], &[u64]) -> Vec<u64>,
+        cost: FieldCost,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        self.use_ticks(cost.cost(size / 8))?;
+        let modulus = self.memory_get(function, args.nth(2), size)?;
+        let modulus =
+            Modulus::from_be_bytes(&modulus).map_err(|err| HostError::failure(function, err))?;
+        let a = self.load_element(function, &modulus, args.nth(0))?;
+        let b = self.load_element(function, &modulus, args.nth(1))?;
+        let c = op(&modulus, &a, &b);
+        self.memory_set(function, args.nth(3), &montgomery::limbs_to_be_bytes(&c))?;
+        Ok(None)
+    }
+
+    /// Applies a binary operation in the preset field identified by the first argument to the
+    /// operands pointed to by the second and third, writing the result to the fourth. `cost` is
+    /// charged before the operands are read.
+    fn field_op(
+        &mut self,
+        function: &str,
+        args: &RuntimeArgs,
+        op: fn(&Modulus, &[u64], &[u64]) -> Vec<u64>,
+        cost: FieldCost,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let modulus = preset_modulus(function, args.nth(0))?;
+        self.use_ticks(cost.cost(modulus.size() as u32 / 8))?;
+        let a = self.load_element(function, &modulus, args.nth(1))?;
+        let b = self.load_element(function, &modulus, args.nth(2))?;
+        let c = op(&modulus, &a, &b);
+        self.memory_set(function, args.nth(3), &montgomery::limbs_to_be_bytes(&c))?;
+        Ok(None)
+    }
+
+    /// Converts the element pointed to by the second argument in the preset field identified by
+    /// the first argument, writing the result to the third. It costs as much as a multiplication.
+    fn field_conversion(
+        &mut self,
+        function: &str,
+        args: &RuntimeArgs,
+        op: fn(&Modulus, &[u64]) -> Vec<u64>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let modulus = preset_modulus(function, args.nth(0))?;
+        self.use_ticks(self.costs.montgomery_mul.cost(modulus.size() as u32 / 8))?;
+        let a = self.load_element(function, &modulus, args.nth(1))?;
+        let c = op(&modulus, &a);
+        self.memory_set(function, args.nth(2), &montgomery::limbs_to_be_bytes(&c))?;
+        Ok(None)
+    }
+
+    /// Reads a big-endian element of the field defined by `modulus` from memory.
+    fn load_element(&self, function: &str, modulus: &Modulus, ptr: u32) -> Result<Vec<u64>, Trap> {
+        let raw = self.memory_get(function, ptr, modulus.size() as u32)?;
+        modulus
+            .element(&raw)
+            .map_err(|err| HostError::failure(function, err).into())
+    }
+}
+
+fn preset_modulus(function: &str, field: u32) -> Result<Modulus, Trap> {
+    Modulus::preset(field)
+        .ok_or_else(|| HostError::failure(function, format!("unknown field {}", field)).into())
+}
+
+/// Reduces a 512-bit intermediate result modulo `m`.
+fn mod_u512(function: &str, value: U512, m: U256) -> Result<U256, Trap> {
+    if m.is_zero() {
+        return Err(HostError::failure(function, "modulus is zero").into());
+    }
+    U256::try_from(value % U512::from(m))
+        .map_err(|_| HostError::failure(function, "modular reduction overflow").into())
+}
+
+/// Recovers the Ethereum address which signed `hash`. The signature is `r || s || v` with `v` being
+/// 27 or 28 as in Eth1 (0 or 1 is accepted too). Returns `None` for invalid signatures.
+fn ecrecover(hash: &[u8; 32], signature: &[u8; 65]) -> Option<[u8; 20]> {
+    let r = U256::from_big_endian(&signature[0..32]);
+    let s = U256::from_big_endian(&signature[32..64]);
+    // The secp256k1 crate silently reduces r and s, but they are invalid outside of [1, order).
+    if r.is_zero() || s.is_zero() || r >= SECP256K1_ORDER || s >= SECP256K1_ORDER {
+        return None;
+    }
+    let recovery_id = match signature[64] {
+        0 | 1 => signature[64],
+        27 | 28 => signature[64] - 27,
+        _ => return None,
+    };
+
+    let mut raw = [0u8; 64];
+    raw.copy_from_slice(&signature[0..64]);
+    let pubkey = secp256k1::recover(
+        &secp256k1::Message::parse(hash),
+        &secp256k1::Signature::parse(&raw),
+        &secp256k1::RecoveryId::parse(recovery_id).ok()?,
+    )
+    .ok()?;
+    // The address is the last 20 bytes of the hash of the uncompressed key without its prefix.
+    let pubkey_hash = tiny_keccak::keccak256(&pubkey.serialize()[1..]);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&pubkey_hash[12..]);
+    Some(address)
+}
+
+impl<'a> Externals for Runtime<'a> {
+    fn invoke_index(
+        &mut self,
+        index: usize,
+        args: RuntimeArgs,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        match index {
+            USETICKS_FUNC_INDEX => {
+                let ticks: u32 = args.nth(0);
+                self.use_ticks(u64::from(ticks))?;
+                Ok(None)
+            }
+            LOADPRESTATEROOT_FUNC_INDEX => {
+                let ptr: u32 = args.nth(0);
+                info!("loadprestateroot to {}", ptr);
+
+                self.memory_set("eth2_loadPreStateRoot", ptr, &self.pre_state.bytes)?;
+
+                Ok(None)
+            }
+            STORAGELOAD_FUNC_INDEX => {
+                let key_ptr: u32 = args.nth(0);
+                let value_ptr: u32 = args.nth(1);
+                self.use_ticks(u64::from(self.costs.storage_load))?;
+
+                let mut key = Bytes32::default();
+                self.memory_get_into("eth2_storageLoad", key_ptr, &mut key.bytes)?;
+                let value = self.storage.get(&key).cloned().unwrap_or_default();
+                self.memory_set("eth2_storageLoad", value_ptr, &value.bytes)?;
+                self.storage_accesses.loads += 1;
+
+                Ok(None)
+            }
+            STORAGESTORE_FUNC_INDEX => {
+                let key_ptr: u32 = args.nth(0);
+                let value_ptr: u32 = args.nth(1);
+                self.use_ticks(u64::from(self.costs.storage_store))?;
+
+                let mut key = Bytes32::default();
+                self.memory_get_into("eth2_storageStore", key_ptr, &mut key.bytes)?;
+                let mut value = Bytes32::default();
+                self.memory_get_into("eth2_storageStore", value_ptr, &mut value.bytes)?;
+                if value == Bytes32::default() {
+                    self.storage.remove(&key);
+                } else {
+                    self.storage.insert(key, value);
+                }
+                self.storage_accesses.stores += 1;
+
+                Ok(None)
+            }
+            SAVEPOSTSTATEROOT_FUNC_INDEX => {
+                let ptr: u32 = args.nth(0);
+                info!("savepoststateroot from {}", ptr);
+
+                let mut post_state = Bytes32::default();
+                self.memory_get_into("eth2_savePostStateRoot", ptr, &mut post_state.bytes)?;
+                self.post_state = post_state;
+
+                Ok(None)
+            }
+            BLOCKDATASIZE_FUNC_INDEX => {
+                let ret: i32 = self.block_data.data.len() as i32;
+                info!("blockdatasize {}", ret);
+                Ok(Some(ret.into()))
+            }
+            BLOCKDATACOPY_FUNC_INDEX => {
+                let ptr: u32 = args.nth(0);
+                let offset: u32 = args.nth(1);
+                let length: u32 = args.nth(2);
+                info!(
+                    "blockdatacopy to {} from {} for {} bytes",
+                    ptr, offset, length
+                );
+
+                let end = offset.checked_add(length).ok_or_else(|| {
+                    HostError::failure("eth2_blockDataCopy", "block data offset overflow")
+                })?;
+                let data = self
+                    .block_data
+                    .data
+                    .get(offset as usize..end as usize)
+                    .ok_or_else(|| {
+                        HostError::failure(
+                            "eth2_blockDataCopy",
+                            format!(
+                                "reading {} bytes from offset {} is past the end of block data ({} bytes)",
+                                length,
+                                offset,
+                                self.block_data.data.len()
+                            ),
+                        )
+                    })?;
+                self.memory_set("eth2_blockDataCopy", ptr, data)?;
+
+                Ok(None)
+            }
+            PUSHNEWDEPOSIT_FUNC_INDEX => {
+                let ptr: u32 = args.nth(0);
+                let length: u32 = args.nth(1);
+                info!("pushnewdeposit from {} for {} bytes", ptr, length);
+
+                let tmp = self.memory_get("eth2_pushNewDeposit", ptr, length)?;
+                debug!("deposit: {}", tmp.to_hex());
+                self.deposits.push(tmp);
+
+                Ok(None)
+            }
+            DEBUG_PRINT32_FUNC => {
+                let value: u32 = args.nth(0);
+                debug!("print.i32: {}", value);
+                Ok(None)
+            }
+            DEBUG_PRINT64_FUNC => {
+                let value: u64 = args.nth(0);
+                debug!("print.i64: {}", value);
+                Ok(None)
+            }
+            DEBUG_PRINTMEM_FUNC => {
+                let ptr: u32 = args.nth(0);
+                let length: u32 = args.nth(1);
+                let buf = self.memory_get("debug_printMem", ptr, length)?;
+                debug!("print: {}", String::from_utf8_lossy(&buf));
+                Ok(None)
+            }
+            DEBUG_PRINTMEMHEX_FUNC => {
+                let ptr: u32 = args.nth(0);
+                let length: u32 = args.nth(1);
+                let buf = self.memory_get("debug_printMemHex", ptr, length)?;
+                debug!("print.hex: {}", buf.to_hex());
+                Ok(None)
+            }
+            BIGNUM_ADD256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_add256", &args)?;
+                let c = a
+                    .checked_add(b)
+                    .ok_or_else(|| HostError::failure("bignum_add256", "addition overflow"))?;
+                self.store_u256("bignum_add256", args.nth(2), c)?;
+                Ok(None)
+            }
+            BIGNUM_SUB256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_sub256", &args)?;
+                let c = a
+                    .checked_sub(b)
+                    .ok_or_else(|| HostError::failure("bignum_sub256", "subtraction underflow"))?;
+                self.store_u256("bignum_sub256", args.nth(2), c)?;
+                Ok(None)
+            }
+            BIGNUM_MUL256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_mul256", &args)?;
+                let c = a.checked_mul(b).ok_or_else(|| {
+                    HostError::failure("bignum_mul256", "multiplication overflow")
+                })?;
+                self.store_u256("bignum_mul256", args.nth(2), c)?;
+                Ok(None)
+            }
+            BIGNUM_DIV256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_div256", &args)?;
+                let c = a
+                    .checked_div(b)
+                    .ok_or_else(|| HostError::failure("bignum_div256", "division by zero"))?;
+                self.store_u256("bignum_div256", args.nth(2), c)?;
+                Ok(None)
+            }
+            BIGNUM_MOD256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_mod256", &args)?;
+                let c = a
+                    .checked_rem(b)
+                    .ok_or_else(|| HostError::failure("bignum_mod256", "division by zero"))?;
+                self.store_u256("bignum_mod256", args.nth(2), c)?;
+                Ok(None)
+            }
+            BIGNUM_ADDMOD256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_addmod256", &args)?;
+                let m = self.load_u256("bignum_addmod256", args.nth(2))?;
+                let c = mod_u512("bignum_addmod256", U512::from(a) + U512::from(b), m)?;
+                self.store_u256("bignum_addmod256", args.nth(3), c)?;
+                Ok(None)
+            }
+            BIGNUM_MULMOD256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_mulmod256", &args)?;
+                let m = self.load_u256("bignum_mulmod256", args.nth(2))?;
+                let c = mod_u512("bignum_mulmod256", a.full_mul(b), m)?;
+                self.store_u256("bignum_mulmod256", args.nth(3), c)?;
+                Ok(None)
+            }
+            BIGNUM_LT256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_lt256", &args)?;
+                Ok(Some(RuntimeValue::I32((a < b) as i32)))
+            }
+            BIGNUM_GT256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_gt256", &args)?;
+                Ok(Some(RuntimeValue::I32((a > b) as i32)))
+            }
+            BIGNUM_EQ256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_eq256", &args)?;
+                Ok(Some(RuntimeValue::I32((a == b) as i32)))
+            }
+            BIGNUM_WRAPPINGADD256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_wrappingAdd256", &args)?;
+                let (c, carry) = a.overflowing_add(b);
+                self.store_u256("bignum_wrappingAdd256", args.nth(2), c)?;
+                Ok(Some(RuntimeValue::I32(carry as i32)))
+            }
+            BIGNUM_WRAPPINGSUB256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_wrappingSub256", &args)?;
+                let (c, borrow) = a.overflowing_sub(b);
+                self.store_u256("bignum_wrappingSub256", args.nth(2), c)?;
+                Ok(Some(RuntimeValue::I32(borrow as i32)))
+            }
+            BIGNUM_WRAPPINGMUL256_FUNC => {
+                self.use_ticks(u64::from(self.costs.bignum))?;
+                let (a, b) = self.load_u256_operands("bignum_wrappingMul256", &args)?;
+                let (c, overflow) = a.overflowing_mul(b);
+                self.store_u256("bignum_wrappingMul256", args.nth(2), c)?;
+                Ok(Some(RuntimeValue::I32(overflow as i32)))
+            }
+            BIGNUM_MULMODMONT256_FUNC => self.modulus_op(
+                "bignum_mulModMont256",
+                32,
+                &args,
+                Modulus::mul,
+                self.costs.montgomery_mul,
+            ),
+            BIGNUM_ADDMODMONT256_FUNC => self.modulus_op(
+                "bignum_addModMont256",
+                32,
+                &args,
+                Modulus::add,
+                self.costs.montgomery_add,
+            ),
+            BIGNUM_SUBMODMONT256_FUNC => self.modulus_op(
+                "bignum_subModMont256",
+                32,
+                &args,
+                Modulus::sub,
+                self.costs.montgomery_add,
+            ),
+            BIGNUM_MULMODMONT384_FUNC => self.modulus_op(
+                "bignum_mulModMont384",
+                48,
+                &args,
+                Modulus::mul,
+                self.costs.montgomery_mul,
+            ),
+            BIGNUM_ADDMODMONT384_FUNC => self.modulus_op(
+                "bignum_addModMont384",
+                48,
+                &args,
+                Modulus::add,
+                self.costs.montgomery_add,
+            ),
+            BIGNUM_SUBMODMONT384_FUNC => self.modulus_op(
+                "bignum_subModMont384",
+                48,
+                &args,
+                Modulus::sub,
+                self.costs.montgomery_add,
+            ),
+            BIGNUM_FIELDMUL_FUNC => self.field_op(
+                "bignum_fieldMul",
+                &args,
+                Modulus::mul,
+                self.costs.montgomery_mul,
+            ),
+            BIGNUM_FIELDADD_FUNC => self.field_op(
+                "bignum_fieldAdd",
+                &args,
+                Modulus::add,
+                self.costs.montgomery_add,
+            ),
+            BIGNUM_FIELDSUB_FUNC => self.field_op(
+                "bignum_fieldSub",
+                &args,
+                Modulus::sub,
+                self.costs.montgomery_add,
+            ),
+            BIGNUM_FIELDTOMONT_FUNC => {
+                self.field_conversion("bignum_fieldToMont", &args, Modulus::to_montgomery)
+            }
+            BIGNUM_FIELDFROMMONT_FUNC => {
+                self.field_conversion("bignum_fieldFromMont", &args, Modulus::from_montgomery)
+            }
+            CRYPTO_KECCAK256_FUNC => {
+                let ptr: u32 = args.nth(0);
+                let length: u32 = args.nth(1);
+                let out_ptr: u32 = args.nth(2);
+                self.use_ticks(self.costs.keccak256.cost(length))?;
+
+                let data = self.memory_get("crypto_keccak256", ptr, length)?;
+                let hash = tiny_keccak::keccak256(&data);
+                self.memory_set("crypto_keccak256", out_ptr, &hash)?;
+
+                Ok(None)
+            }
+            CRYPTO_SHA256_FUNC => {
+                let ptr: u32 = args.nth(0);
+                let length: u32 = args.nth(1);
+                let out_ptr: u32 = args.nth(2);
+                self.use_ticks(self.costs.sha256.cost(length))?;
+
+                let data = self.memory_get("crypto_sha256", ptr, length)?;
+                let hash = Sha256::digest(&data);
+                self.memory_set("crypto_sha256", out_ptr, &hash)?;
+
+                Ok(None)
+            }
+            CRYPTO_BLSVERIFY_FUNC => {
+                let pubkey_ptr: u32 = args.nth(0);
+                let message_ptr: u32 = args.nth(1);
+                let message_length: u32 = args.nth(2);
+                let signature_ptr: u32 = args.nth(3);
+                self.use_ticks(u64::from(self.costs.bls_verify))?;
+
+                let function = "crypto_blsVerify";
+                let pubkeys = self.load_bls_pubkeys(function, pubkey_ptr, 1)?;
+                let message = self.memory_get(function, message_ptr, message_length)?;
+                let signature = self.load_bls_signature(function, signature_ptr)?;
+                let valid = match (pubkeys, signature) {
+                    (Some(pubkeys), Some(signature)) => {
+                        signature.verify(false, &message, BLS_DST, &[], &pubkeys[0], false)
+                            == BLST_ERROR::BLST_SUCCESS
+                    }
+                    _ => false,
+                };
+
+                Ok(Some(RuntimeValue::I32(valid as i32)))
+            }
+            CRYPTO_BLSAGGREGATEVERIFY_FUNC => {
+                let pubkeys_ptr: u32 = args.nth(0);
+                let count: u32 = args.nth(1);
+                let messages_ptr: u32 = args.nth(2);
+                let message_length: u32 = args.nth(3);
+                let signature_ptr: u32 = args.nth(4);
+                self.use_ticks(self.costs.bls_aggregate_verify.cost(count))?;
+
+                let function = "crypto_blsAggregateVerify";
+                let pubkeys = self.load_bls_pubkeys(function, pubkeys_ptr, count)?;
+                let messages_length = count.checked_mul(message_length).ok_or_else(|| {
+                    HostError::failure(function, "messages exceed the address space")
+                })?;
+                let messages = self.memory_get(function, messages_ptr, messages_length)?;
+                let signature = self.load_bls_signature(function, signature_ptr)?;
+                let valid = match (pubkeys, signature) {
+                    (Some(pubkeys), Some(signature)) if count > 0 => {
+                        let pubkeys: Vec<&bls::PublicKey> = pubkeys.iter().collect();
+                        let messages: Vec<&[u8]> = if message_length == 0 {
+                            vec![&[]; count as usize]
+                        } else {
+                            messages.chunks(message_length as usize).collect()
+                        };
+                        signature.aggregate_verify(false, &messages, BLS_DST, &pubkeys, false)
+                            == BLST_ERROR::BLST_SUCCESS
+                    }
+                    _ => false,
+                };
+
+                Ok(Some(RuntimeValue::I32(valid as i32)))
+            }
+            CRYPTO_BLSFASTAGGREGATEVERIFY_FUNC => {
+                let pubkeys_ptr: u32 = args.nth(0);
+                let count: u32 = args.nth(1);
+                let message_ptr: u32 = args.nth(2);
+                let message_length: u32 = args.nth(3);
+                let signature_ptr: u32 = args.nth(4);
+                self.use_ticks(self.costs.bls_fast_aggregate_verify.cost(count))?;
+
+                let function = "crypto_blsFastAggregateVerify";
+                let pubkeys = self.load_bls_pubkeys(function, pubkeys_ptr, count)?;
+                let message = self.memory_get(function, message_ptr, message_length)?;
+                let signature = self.load_bls_signature(function, signature_ptr)?;
+                let valid = match (pubkeys, signature) {
+                    (Some(pubkeys), Some(signature)) if count > 0 => {
+                        let pubkeys: Vec<&bls::PublicKey> = pubkeys.iter().collect();
+                        signature.fast_aggregate_verify(false, &message, BLS_DST, &pubkeys)
+                            == BLST_ERROR::BLST_SUCCESS
+                    }
+                    _ => false,
+                };
+
+                Ok(Some(RuntimeValue::I32(valid as i32)))
+            }
+            CRYPTO_ECRECOVER_FUNC => {
+                let hash_ptr: u32 = args.nth(0);
+                let signature_ptr: u32 = args.nth(1);
+                let out_ptr: u32 = args.nth(2);
+                self.use_ticks(u64::from(self.costs.ecrecover))?;
+
+                let mut hash = [0u8; 32];
+                self.memory_get_into("crypto_ecrecover", hash_ptr, &mut hash)?;
+                let mut signature = [0u8; 65];
+                self.memory_get_into("crypto_ecrecover", signature_ptr, &mut signature)?;
+                match ecrecover(&hash, &signature) {
+                    Some(address) => {
+                        self.memory_set("crypto_ecrecover", out_ptr, &address)?;
+                        Ok(Some(RuntimeValue::I32(1)))
+                    }
+                    None => Ok(Some(RuntimeValue::I32(0))),
+                }
+            }
+            CRYPTO_BN128ADD_FUNC => {
+                let a_ptr: u32 = args.nth(0);
+                let b_ptr: u32 = args.nth(1);
+                let out_ptr: u32 = args.nth(2);
+                self.use_ticks(u64::from(self.costs.bn128_add))?;
+
+                let a = self.memory_get("crypto_bn128Add", a_ptr, bn128::G1_SIZE as u32)?;
+                let b = self.memory_get("crypto_bn128Add", b_ptr, bn128::G1_SIZE as u32)?;
+                match bn128::add(&a, &b) {
+                    Some(sum) => {
+                        self.memory_set("crypto_bn128Add", out_ptr, &sum)?;
+                        Ok(Some(RuntimeValue::I32(1)))
+                    }
+                    None => Ok(Some(RuntimeValue::I32(0))),
+                }
+            }
+            CRYPTO_BN128MUL_FUNC => {
+                let point_ptr: u32 = args.nth(0);
+                let scalar_ptr: u32 = args.nth(1);
+                let out_ptr: u32 = args.nth(2);
+                self.use_ticks(u64::from(self.costs.bn128_mul))?;
+
+                let point = self.memory_get("crypto_bn128Mul", point_ptr, bn128::G1_SIZE as u32)?;
+                let scalar =
+                    self.memory_get("crypto_bn128Mul", scalar_ptr, bn128::SCALAR_SIZE as u32)?;
+                match bn128::mul(&point, &scalar) {
+                    Some(product) => {
+                        self.memory_set("crypto_bn128Mul", out_ptr, &product)?;
+                        Ok(Some(RuntimeValue::I32(1)))
+                    }
+                    None => Ok(Some(RuntimeValue::I32(0))),
+                }
+            }
+            CRYPTO_BN128PAIRING_FUNC => {
+                let pairs_ptr: u32 = args.nth(0);
+                let count: u32 = args.nth(1);
+                self.use_ticks(self.costs.bn128_pairing.cost(count))?;
+
+                let length = count.checked_mul(bn128::PAIR_SIZE as u32).ok_or_else(|| {
+                    HostError::failure("crypto_bn128Pairing", format!("too many pairs: {}", count))
+                })?;
+                let pairs = self.memory_get("crypto_bn128Pairing", pairs_ptr, length)?;
+                let result = match bn128::pairing_check(&pairs) {
+                    Some(true) => 1,
+                    Some(false) => 0,
+                    None => -1,
+                };
+
+                Ok(Some(RuntimeValue::I32(result)))
+            }
+            SSZ_HASHTREEROOT_FUNC => {
+                let ptr: u32 = args.nth(0);
+                let length: u32 = args.nth(1);
+                let out_ptr: u32 = args.nth(2);
+                let chunks = u64::from(length).div_ceil(ssz::CHUNK_SIZE as u64);
+                self.use_ticks(self.costs.ssz_hash_tree_root.cost(chunks))?;
+
+                let data = self.memory_get("ssz_hashTreeRoot", ptr, length)?;
+                let root = ssz::hash_tree_root(&data);
+                self.memory_set("ssz_hashTreeRoot", out_ptr, &root)?;
+
+                Ok(None)
+            }
+            SSZ_VERIFYMULTIPROOF_FUNC => {
+                let root_ptr: u32 = args.nth(0);
+                let indices_ptr: u32 = args.nth(1);
+                let leaves_ptr: u32 = args.nth(2);
+                let count: u32 = args.nth(3);
+                let proof_ptr: u32 = args.nth(4);
+                let proof_count: u32 = args.nth(5);
+                let chunks = u64::from(count) + u64::from(proof_count);
+                self.use_ticks(self.costs.ssz_verify_multiproof.cost(chunks))?;
+
+                let function = "ssz_verifyMultiproof";
+                let root = self.load_chunks(function, root_ptr, 1)?;
+                let indices_length = count.checked_mul(8).ok_or_else(|| {
+                    HostError::failure(function, format!("too many leaves: {}", count))
+                })?;
+                let indices: Vec<u64> = self
+                    .memory_get(function, indices_ptr, indices_length)?
+                    .chunks(8)
+                    .map(|raw| {
+                        let mut index = [0u8; 8];
+                        index.copy_from_slice(raw);
+                        u64::from_le_bytes(index)
+                    })
+                    .collect();
+                let leaves = self.load_chunks(function, leaves_ptr, count)?;
+                let proof = self.load_chunks(function, proof_ptr, proof_count)?;
+                let valid = ssz::verify_multiproof(&root[0], &indices, &leaves, &proof);
+
+                Ok(Some(RuntimeValue::I32(valid as i32)))
+            }
+            _ => Err(
+                HostError::failure("unknown", format!("unknown function index {}", index)).into(),
+            ),
+        }
+    }
+}
+
+// TODO: remove this and rely on Eth2ImportResolver and DebugImportResolver
+struct RuntimeModuleImportResolver;
+
+/// Resolves only the `gas` function imported by the metering injected into libraries.
+struct MeteringImportResolver;
+
+impl ModuleImportResolver for MeteringImportResolver {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        _signature: &Signature,
+    ) -> Result<FuncRef, InterpreterError> {
+        match field_name {
+            "gas" => Ok(FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], None),
+                USETICKS_FUNC_INDEX,
+            )),
+            _ => Err(InterpreterError::Function(format!(
+                "host module doesn't export function with name {}",
+                field_name
+            ))),
+        }
+    }
+}
+
+impl ModuleImportResolver for RuntimeModuleImportResolver {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        _signature: &Signature,
+    ) -> Result<FuncRef, InterpreterError> {
+        let func_ref = match field_name {
+            "eth2_useTicks" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], None),
+                USETICKS_FUNC_INDEX,
+            ),
+            // This is imported by the injected metering.
+            "gas" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], None),
+                USETICKS_FUNC_INDEX,
+            ),
+            "eth2_loadPreStateRoot" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], None),
+                LOADPRESTATEROOT_FUNC_INDEX,
+            ),
+            "eth2_storageLoad" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], None),
+                STORAGELOAD_FUNC_INDEX,
+            ),
+            "eth2_storageStore" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], None),
+                STORAGESTORE_FUNC_INDEX,
+            ),
+            "eth2_blockDataSize" => FuncInstance::alloc_host(
+                Signature::new(&[][..], Some(ValueType::I32)),
+                BLOCKDATASIZE_FUNC_INDEX,
+            ),
+            "eth2_blockDataCopy" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BLOCKDATACOPY_FUNC_INDEX,
+            ),
+            "eth2_savePostStateRoot" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], None),
+                SAVEPOSTSTATEROOT_FUNC_INDEX,
+            ),
+            "eth2_pushNewDeposit" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], None),
+                PUSHNEWDEPOSIT_FUNC_INDEX,
+            ),
+            "debug_print32" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], None),
+                DEBUG_PRINT32_FUNC,
+            ),
+            "debug_print64" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I64][..], None),
+                DEBUG_PRINT64_FUNC,
+            ),
+            "debug_printMem" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], None),
+                DEBUG_PRINTMEM_FUNC,
+            ),
+            "debug_printMemHex" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], None),
+                DEBUG_PRINTMEMHEX_FUNC,
+            ),
+            "bignum_add256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_ADD256_FUNC,
+            ),
+            "bignum_sub256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_SUB256_FUNC,
+            ),
+            "bignum_mul256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_MUL256_FUNC,
+            ),
+            "bignum_div256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_DIV256_FUNC,
+            ),
+            "bignum_mod256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_MOD256_FUNC,
+            ),
+            "bignum_addmod256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_ADDMOD256_FUNC,
+            ),
+            "bignum_mulmod256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_MULMOD256_FUNC,
+            ),
+            "bignum_lt256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                BIGNUM_LT256_FUNC,
+            ),
+            "bignum_gt256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                BIGNUM_GT256_FUNC,
+            ),
+            "bignum_eq256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                BIGNUM_EQ256_FUNC,
+            ),
+            "bignum_wrappingAdd256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                BIGNUM_WRAPPINGADD256_FUNC,
+            ),
+            "bignum_wrappingSub256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                BIGNUM_WRAPPINGSUB256_FUNC,
+            ),
+            "bignum_wrappingMul256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                BIGNUM_WRAPPINGMUL256_FUNC,
+            ),
+            "bignum_mulModMont256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_MULMODMONT256_FUNC,
+            ),
+            "bignum_addModMont256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_ADDMODMONT256_FUNC,
+            ),
+            "bignum_subModMont256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_SUBMODMONT256_FUNC,
+            ),
+            "bignum_mulModMont384" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_MULMODMONT384_FUNC,
+            ),
+            "bignum_addModMont384" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_ADDMODMONT384_FUNC,
+            ),
+            "bignum_subModMont384" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_SUBMODMONT384_FUNC,
+            ),
+            "bignum_fieldMul" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_FIELDMUL_FUNC,
+            ),
+            "bignum_fieldAdd" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_FIELDADD_FUNC,
+            ),
+            "bignum_fieldSub" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_FIELDSUB_FUNC,
+            ),
+            "bignum_fieldToMont" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_FIELDTOMONT_FUNC,
+            ),
+            "bignum_fieldFromMont" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_FIELDFROMMONT_FUNC,
+            ),
+            "crypto_keccak256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                CRYPTO_KECCAK256_FUNC,
+            ),
+            "crypto_sha256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                CRYPTO_SHA256_FUNC,
+            ),
+            "crypto_blsVerify" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_BLSVERIFY_FUNC,
+            ),
+            "crypto_blsAggregateVerify" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_BLSAGGREGATEVERIFY_FUNC,
+            ),
+            "crypto_blsFastAggregateVerify" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_BLSFASTAGGREGATEVERIFY_FUNC,
+            ),
+            "crypto_ecrecover" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_ECRECOVER_FUNC,
+            ),
+            "crypto_bn128Add" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_BN128ADD_FUNC,
+            ),
+            "crypto_bn128Mul" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_BN128MUL_FUNC,
+            ),
+            "crypto_bn128Pairing" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                CRYPTO_BN128PAIRING_FUNC,
+            ),
+            "ssz_hashTreeRoot" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                SSZ_HASHTREEROOT_FUNC,
+            ),
+            "ssz_verifyMultiproof" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    Some(ValueType::I32),
+                ),
+                SSZ_VERIFYMULTIPROOF_FUNC,
+            ),
+            _ => {
+                return Err(InterpreterError::Function(format!(
+                    "host module doesn't export function with name {}",
+                    field_name
+                )))
+            }
+        };
+        Ok(func_ref)
+    }
+}
+
+struct Eth2ImportResolver;
+
+impl ModuleImportResolver for Eth2ImportResolver {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        _signature: &Signature,
+    ) -> Result<FuncRef, InterpreterError> {
+        let func_ref = match field_name {
+            "useTicks" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], None),
+                USETICKS_FUNC_INDEX,
+            ),
+            "loadPreStateRoot" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], None),
+                LOADPRESTATEROOT_FUNC_INDEX,
+            ),
+            "storageLoad" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], None),
+                STORAGELOAD_FUNC_INDEX,
+            ),
+            "storageStore" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], None),
+                STORAGESTORE_FUNC_INDEX,
+            ),
+            "blockDataSize" => FuncInstance::alloc_host(
+                Signature::new(&[][..], Some(ValueType::I32)),
+                BLOCKDATASIZE_FUNC_INDEX,
+            ),
+            "blockDataCopy" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BLOCKDATACOPY_FUNC_INDEX,
+            ),
+            "savePostStateRoot" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], None),
+                SAVEPOSTSTATEROOT_FUNC_INDEX,
+            ),
+            "pushNewDeposit" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], None),
+                PUSHNEWDEPOSIT_FUNC_INDEX,
+            ),
+            _ => {
+                return Err(InterpreterError::Function(format!(
+                    "host module doesn't export function with name {}",
+                    field_name
+                )))
+            }
+        };
+        Ok(func_ref)
+    }
+}
+
+struct BignumImportResolver;
+
+impl ModuleImportResolver for BignumImportResolver {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        _signature: &Signature,
+    ) -> Result<FuncRef, InterpreterError> {
+        let func_ref = match field_name {
+            "add256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_ADD256_FUNC,
+            ),
+            "sub256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_SUB256_FUNC,
+            ),
+            "mul256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_MUL256_FUNC,
+            ),
+            "div256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_DIV256_FUNC,
+            ),
+            "mod256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_MOD256_FUNC,
+            ),
+            "addmod256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_ADDMOD256_FUNC,
+            ),
+            "mulmod256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_MULMOD256_FUNC,
+            ),
+            "lt256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                BIGNUM_LT256_FUNC,
+            ),
+            "gt256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                BIGNUM_GT256_FUNC,
+            ),
+            "eq256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                BIGNUM_EQ256_FUNC,
+            ),
+            "wrappingAdd256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                BIGNUM_WRAPPINGADD256_FUNC,
+            ),
+            "wrappingSub256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                BIGNUM_WRAPPINGSUB256_FUNC,
+            ),
+            "wrappingMul256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                BIGNUM_WRAPPINGMUL256_FUNC,
+            ),
+            "mulModMont256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_MULMODMONT256_FUNC,
+            ),
+            "addModMont256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_ADDMODMONT256_FUNC,
+            ),
+            "subModMont256" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_SUBMODMONT256_FUNC,
+            ),
+            "mulModMont384" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_MULMODMONT384_FUNC,
+            ),
+            "addModMont384" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_ADDMODMONT384_FUNC,
+            ),
+            "subModMont384" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_SUBMODMONT384_FUNC,
+            ),
+            "fieldMul" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_FIELDMUL_FUNC,
+            ),
+            "fieldAdd" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_FIELDADD_FUNC,
+            ),
+            "fieldSub" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    None,
+                ),
+                BIGNUM_FIELDSUB_FUNC,
+            ),
+            "fieldToMont" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_FIELDTOMONT_FUNC,
+            ),
+            "fieldFromMont" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                BIGNUM_FIELDFROMMONT_FUNC,
+            ),
+            _ => {
+                return Err(InterpreterError::Function(format!(
+                    "host module doesn't export function with name {}",
+                    field_name
+                )))
+            }
+        };
+        Ok(func_ref)
+    }
+}
+
+struct DebugImportResolver;
+
+impl ModuleImportResolver for DebugImportResolver {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        _signature: &Signature,
+    ) -> Result<FuncRef, InterpreterError> {
+        let func_ref = match field_name {
+            "print32" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], None),
+                DEBUG_PRINT32_FUNC,
+            ),
+            "print64" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I64][..], None),
+                DEBUG_PRINT64_FUNC,
+            ),
+            "printMem" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], None),
+                DEBUG_PRINTMEM_FUNC,
+            ),
+            "printMemHex" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], None),
+                DEBUG_PRINTMEMHEX_FUNC,
+            ),
+            _ => {
+                return Err(InterpreterError::Function(format!(
+                    "host module doesn't export function with name {}",
+                    field_name
+                )))
+            }
+        };
+        Ok(func_ref)
+    }
+}
+
+struct CryptoImportResolver;
+
+impl ModuleImportResolver for CryptoImportResolver {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        _signature: &Signature,
+    ) -> Result<FuncRef, InterpreterError> {
+        let func_ref = match field_name {
+            "keccak256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                CRYPTO_KECCAK256_FUNC,
+            ),
+            "sha256" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                CRYPTO_SHA256_FUNC,
+            ),
+            "blsVerify" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_BLSVERIFY_FUNC,
+            ),
+            "blsAggregateVerify" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_BLSAGGREGATEVERIFY_FUNC,
+            ),
+            "blsFastAggregateVerify" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_BLSFASTAGGREGATEVERIFY_FUNC,
+            ),
+            "ecrecover" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_ECRECOVER_FUNC,
+            ),
+            "bn128Add" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_BN128ADD_FUNC,
+            ),
+            "bn128Mul" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                CRYPTO_BN128MUL_FUNC,
+            ),
+            "bn128Pairing" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
+                CRYPTO_BN128PAIRING_FUNC,
+            ),
+            _ => {
+                return Err(InterpreterError::Function(format!(
+                    "host module doesn't export function with name {}",
+                    field_name
+                )))
+            }
+        };
+        Ok(func_ref)
+    }
+}
+
+struct SszImportResolver;
+
+impl ModuleImportResolver for SszImportResolver {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        _signature: &Signature,
+    ) -> Result<FuncRef, InterpreterError> {
+        let func_ref = match field_name {
+            "hashTreeRoot" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32, ValueType::I32, ValueType::I32][..], None),
+                SSZ_HASHTREEROOT_FUNC,
+            ),
+            "verifyMultiproof" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                        ValueType::I32,
+                    ][..],
+                    Some(ValueType::I32),
+                ),
+                SSZ_VERIFYMULTIPROOF_FUNC,
+            ),
+            _ => {
+                return Err(InterpreterError::Function(format!(
+                    "host module doesn't export function with name {}",
+                    field_name
+                )))
+            }
+        };
+        Ok(func_ref)
+    }
+}
+
+pub const BYTES_PER_SHARD_BLOCK_BODY: usize = 16384;
+pub const ZERO_HASH: Bytes32 = Bytes32 { bytes: [0u8; 32] };
+
+#[derive(Default, PartialEq, Clone)]
+pub struct Hash(pub [u8; 32]);
+
+impl fmt::Debug for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.to_hex())
+    }
+}
+
+#[derive(Clone)]
+pub struct BLSPubKey(pub [u8; 48]);
+
+impl PartialEq for BLSPubKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0[..] == other.0[..]
+    }
+}
+
+impl Default for BLSPubKey {
+    fn default() -> Self {
+        BLSPubKey([0u8; 48])
+    }
+}
+
+impl fmt::Debug for BLSPubKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.to_hex())
+    }
+}
+
+#[derive(Clone)]
+pub struct BLSSignature(pub [u8; 96]);
+
+impl PartialEq for BLSSignature {
+    fn eq(&self, other: &Self) -> bool {
+        self.0[..] == other.0[..]
+    }
+}
+
+impl Default for BLSSignature {
+    fn default() -> Self {
+        BLSSignature([0u8; 96])
+    }
+}
+
+impl fmt::Debug for BLSSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.to_hex())
+    }
+}
+
+/// These are Phase 0 structures.
+/// https://github.com/ethereum/eth2.0-specs/blob/dev/specs/core/0_beacon-chain.md
+/// basically this is a little-endian tightly packed representation of those fields.
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct Deposit {
+    pub pubkey: BLSPubKey,
+    pub withdrawal_credentials: Hash,
+    pub amount: u64,
+    pub signature: BLSSignature,
+}
+
+impl TryFrom<Vec<u8>> for Deposit {
+    type Error = ScoutError;
+    fn try_from(input: Vec<u8>) -> Result<Self, Self::Error> {
+        if input.len() != 184 {
+            return Err(ScoutError::InvalidInput(
+                "Deposit must be exactly 184 bytes long".to_string(),
+            ));
+        }
+        let mut raw_pubkey = [0u8; 48];
+        raw_pubkey.copy_from_slice(&input[0..48]);
+        let mut raw_hash = [0u8; 32];
+        raw_hash.copy_from_slice(&input[48..80]);
+        let mut raw_amount = [0u8; 8];
+        raw_amount.copy_from_slice(&input[80..88]);
+        let mut raw_signature = [0u8; 96];
+        raw_signature.copy_from_slice(&input[88..184]);
+        Ok(Deposit {
+            pubkey: BLSPubKey(raw_pubkey),
+            withdrawal_credentials: Hash(raw_hash),
+            amount: u64::from_be_bytes(raw_amount),
+            signature: BLSSignature(raw_signature),
+        })
+    }
+}
+
+/// These are Phase 2 Proposal 2 structures.
+
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct ExecutionScript {
+    pub code: Vec<u8>,
+}
+
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct BeaconState {
+    pub execution_scripts: Vec<ExecutionScript>,
+    pub libraries: Vec<Library>,
+}
+
+/// Shards are Phase 1 structures.
+/// https://github.com/ethereum/eth2.0-specs/blob/dev/specs/core/1_shard-data-chains.md
+
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct ShardBlockHeader {}
+
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct ShardBlockBody {
+    pub data: Vec<u8>,
+}
+
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct ShardBlock {
+    pub env: u64, // This is added by Phase 2 Proposal 2
+    pub data: ShardBlockBody,
+    // TODO: add missing fields
+}
+
+#[derive(Default, PartialEq, Clone, Debug)]
+pub struct ShardState {
+    pub exec_env_states: Vec<Bytes32>,
+    /// The storage of each execution environment. Environments without an entry have empty
+    /// storage.
+    pub exec_env_storage: Vec<Storage>,
+    pub slot: u64,
+    pub parent_block: ShardBlockHeader,
+    // TODO: add missing field
+    // latest_state_roots: [bytes32, LATEST_STATE_ROOTS_LEMGTH]
+}
+
+impl fmt::Display for ShardBlockBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.data.to_hex())
+    }
+}
+
+impl fmt::Display for ShardBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Shard block for environment {} with data {}",
+            self.env, self.data
+        )
+    }
+}
+
+impl fmt::Display for ShardState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let states: Vec<String> = self
+            .exec_env_states
+            .iter()
+            .map(|x| x.bytes.to_hex())
+            .collect();
+        write!(
+            f,
+            "Shard slot {} with environment states: {:?}",
+            self.slot, states
+        )
+    }
+}
+
+/// Executes an execution script against a pre-state root and block body with a budget of `ticks`,
+/// metered according to `costs`, returning the post-state root and the raw deposit receipts pushed
+/// by the script.
+pub fn execute_code(
+    code: &[u8],
+    libraries: &[Library],
+    pre_state: &Bytes32,
+    block_data: &ShardBlockBody,
+    ticks: u32,
+    costs: &TickCosts,
+) -> Result<ExecutionResult, ScoutError> {
+    execute_code_with_storage(
+        code,
+        libraries,
+        pre_state,
+        &Storage::new(),
+        block_data,
+        ticks,
+        costs,
+    )
+}
+
+/// Like [`execute_code`], but with the script having access to `storage`. The storage after
+/// execution is returned as part of the result.
+pub fn execute_code_with_storage(
+    code: &[u8],
+    libraries: &[Library],
+    pre_state: &Bytes32,
+    storage: &Storage,
+    block_data: &ShardBlockBody,
+    ticks: u32,
+    costs: &TickCosts,
+) -> Result<ExecutionResult, ScoutError> {
+    debug!(
+        "Executing codesize({}) and data: {}",
+        code.len(),
+        block_data
+    );
+
+    let mut runtime = Runtime::new(
+        code, libraries, pre_state, storage, block_data, ticks, costs,
+    );
+    runtime.execute()
+}
+
+/// Executes `block` (if any) against the execution environment it names with a budget of `ticks`,
+/// updating the environment state root in `state` and returning the deposit receipts produced.
+///
+/// If processing fails `state` is left unchanged.
+pub fn process_shard_block(
+    state: &mut ShardState,
+    beacon_state: &BeaconState,
+    block: Option<ShardBlock>,
+    ticks: u32,
+    costs: &TickCosts,
+) -> Result<ShardBlockResult, ScoutError> {
+    // println!("Beacon state: {:#?}", beacon_state);
+
+    info!("Pre-execution: {}", state);
+
+    // TODO: implement state root handling
+
+    let result = if let Some(block) = block {
+        info!("Executing block: {}", block);
+
+        // The execution environment identifier
+        let env = block.env as usize; // FIXME: usize can be 32-bit
+        let code = &beacon_state
+            .execution_scripts
+            .get(env)
+            .ok_or_else(|| {
+                ScoutError::InvalidInput(format!("Unknown execution environment: {}", env))
+            })?
+            .code;
+
+        // Set post states to empty for any holes
+        // for x in 0..env {
+        //     state.exec_env_states.push(ZERO_HASH)
+        // }
+        let pre_state = state.exec_env_states.get(env).ok_or_else(|| {
+            ScoutError::InvalidInput(format!("Missing state for execution environment: {}", env))
+        })?;
+        let empty_storage = Storage::new();
+        let storage = state.exec_env_storage.get(env).unwrap_or(&empty_storage);
+        let result = execute_code_with_storage(
+            code,
+            &beacon_state.libraries,
+            pre_state,
+            storage,
+            &block.data,
+            ticks,
+            costs,
+        )?;
+
+        // Decode deposits before touching the state, so that a failing block leaves it unchanged.
+        // Malformed deposits are the fault of the script, so they fail the block.
+        let deposits: Result<Vec<Deposit>, _> = result
+            .deposits
+            .into_iter()
+            .map(|deposit| deposit.try_into())
+            .collect();
+        let deposits = deposits.map_err(|err| ScoutError::HostCall {
+            function: "eth2_pushNewDeposit".to_string(),
+            message: err.to_string(),
+        })?;
+
+        state.exec_env_states[env] = result.post_state;
+        if state.exec_env_storage.len() <= env {
+            state.exec_env_storage.resize(env + 1, Storage::new());
+        }
+        state.exec_env_storage[env] = result.post_storage;
+
+        ShardBlockResult {
+            deposit_receipts: deposits,
+            ticks_used: result.ticks_used,
+            ticks_left: result.ticks_left,
+            storage_accesses: result.storage_accesses,
+        }
+    } else {
+        ShardBlockResult {
+            ticks_left: ticks,
+            ..Default::default()
+        }
+    };
+
+    // TODO: implement state + deposit root handling
+
+    info!(
+        "Post-execution deposit receipts: {:?}",
+        result.deposit_receipts
+    );
+    info!(
+        "Post-execution ticks used: {} (left: {})",
+        result.ticks_used, result.ticks_left
+    );
+    info!("Post-execution: {}", state);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_block_keeps_state() {
+        let mut state = ShardState {
+            exec_env_states: vec![Bytes32::from([1u8; 32])],
+            ..Default::default()
+        };
+        let result = process_shard_block(
+            &mut state,
+            &BeaconState::default(),
+            None,
+            DEFAULT_TICKS,
+            &TickCosts::default(),
+        )
+        .unwrap();
+        assert!(result.deposit_receipts.is_empty());
+        assert_eq!(result.ticks_left, DEFAULT_TICKS);
+        assert_eq!(state.exec_env_states, vec![Bytes32::from([1u8; 32])]);
+    }
+
+    #[test]
+    fn unknown_execution_environment() {
+        let mut state = ShardState::default();
+        let block = ShardBlock {
+            env: 1,
+            data: ShardBlockBody::default(),
+        };
+        assert!(process_shard_block(
+            &mut state,
+            &BeaconState::default(),
+            Some(block),
+            DEFAULT_TICKS,
+            &TickCosts::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn failing_block_keeps_state() {
+        // Saves a new state root, then pushes a deposit which is too short.
+        let code = wat::parse_str(
+            r#"(module
+                (import "eth2" "savePostStateRoot" (func $save (param i32)))
+                (import "eth2" "pushNewDeposit" (func $push (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "\ff")
+                (func (export "main")
+                    (call $save (i32.const 0))
+                    (call $push (i32.const 0) (i32.const 32))))"#,
+        )
+        .unwrap();
+        let beacon_state = BeaconState {
+            execution_scripts: vec![ExecutionScript { code }],
+            libraries: Vec::new(),
+        };
+        let mut state = ShardState {
+            exec_env_states: vec![Bytes32::from([1u8; 32])],
+            ..Default::default()
+        };
+        let block = ShardBlock {
+            env: 0,
+            data: ShardBlockBody::default(),
+        };
+        let ret = process_shard_block(
+            &mut state,
+            &beacon_state,
+            Some(block),
+            DEFAULT_TICKS,
+            &TickCosts::default(),
+        );
+        assert!(ret.unwrap_err().is_block_failure());
+        assert_eq!(state.exec_env_states, vec![Bytes32::from([1u8; 32])]);
+    }
+
+    #[test]
+    fn invalid_code() {
+        let ret = execute_code(
+            b"garbage",
+            &[],
+            &ZERO_HASH,
+            &ShardBlockBody::default(),
+            DEFAULT_TICKS,
+            &TickCosts::default(),
+        );
+        assert!(matches!(ret, Err(ScoutError::Decode(_))));
+    }
+
+    #[test]
+    fn missing_memory_export() {
+        let code = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        let ret = execute_code(
+            &code,
+            &[],
+            &ZERO_HASH,
+            &ShardBlockBody::default(),
+            DEFAULT_TICKS,
+            &TickCosts::default(),
+        );
+        assert!(matches!(ret, Err(ScoutError::Link(_))));
+    }
+
+    #[test]
+    fn host_error_trap() {
+        let trap: Trap = HostError::OutOfTicks.into();
+        assert!(matches!(trap.into(), ScoutError::OutOfTicks));
+        let trap: Trap = HostError::failure("eth2_blockDataCopy", "out of bounds").into();
+        assert!(match trap.into() {
+            ScoutError::HostCall { function, .. } => function == "eth2_blockDataCopy",
+            _ => false,
+        });
+    }
+}
@@ -0,0 +1,212 @@
+use crate::ScoutError;
+use parity_wasm::elements::Module;
+use pwasm_utils::rules::{InstructionType, Metering, Set};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The tick costs charged by the metering injected into execution scripts and by host functions.
+///
+/// Instruction costs are accounted per basic block: on entering a block the cost of all the
+/// instructions in it is charged against the same tick budget `eth2_useTicks` draws from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TickCosts {
+    /// Whether to inject metering at all. Without it only `eth2_useTicks` and host functions
+    /// consume ticks.
+    pub enabled: bool,
+    /// The cost of instructions without an entry in `instructions`.
+    pub regular: u32,
+    /// The cost of each page of memory grown by `memory.grow`.
+    pub grow_memory: u32,
+    /// The cost of instruction classes, keyed by the names `pwasm_utils` uses: `bit`, `add`,
+    /// `mul`, `div`, `load`, `store`, `const`, `local`, `global`, `flow`, `integer_comp`,
+    /// `float_comp`, `float`, `conversion`, `float_conversion`, `reinterpret`, `unreachable`,
+    /// `nop`, `current_mem` and `grow_mem`.
+    pub instructions: BTreeMap<String, u32>,
+    /// The cost of `eth2.storageLoad`.
+    pub storage_load: u32,
+    /// The cost of `eth2.storageStore`.
+    pub storage_store: u32,
+    /// The cost of each of the 256-bit `bignum` arithmetic and comparison functions.
+    pub bignum: u32,
+    /// The cost of the `bignum` additions and subtractions in Montgomery form, per 64-bit limb of
+    /// the modulus.
+    pub montgomery_add: FieldCost,
+    /// The cost of the `bignum` multiplications in Montgomery form and of the conversions to and
+    /// from it, per 64-bit limb of the modulus.
+    pub montgomery_mul: FieldCost,
+    /// The cost of `crypto.keccak256`.
+    pub keccak256: HashCost,
+    /// The cost of `crypto.sha256`.
+    pub sha256: HashCost,
+    /// The cost of `crypto.blsVerify`.
+    pub bls_verify: u32,
+    /// The cost of `crypto.blsAggregateVerify`.
+    pub bls_aggregate_verify: AggregateCost,
+    /// The cost of `crypto.blsFastAggregateVerify`.
+    pub bls_fast_aggregate_verify: AggregateCost,
+    /// The cost of `crypto.ecrecover`.
+    pub ecrecover: u32,
+    /// The cost of `crypto.bn128Add`.
+    pub bn128_add: u32,
+    /// The cost of `crypto.bn128Mul`.
+    pub bn128_mul: u32,
+    /// The cost of `crypto.bn128Pairing`.
+    pub bn128_pairing: PairingCost,
+    /// The cost of `ssz.hashTreeRoot`, per 32-byte chunk of input.
+    pub ssz_hash_tree_root: MerkleCost,
+    /// The cost of `ssz.verifyMultiproof`, per leaf and proof node.
+    pub ssz_verify_multiproof: MerkleCost,
+}
+
+/// The cost of a field operation with a modulus of `limbs` 64-bit limbs, which is
+/// `base + limbs * per_limb`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FieldCost {
+    pub base: u32,
+    pub per_limb: u32,
+}
+
+impl FieldCost {
+    pub fn cost(&self, limbs: u32) -> u64 {
+        u64::from(self.base) + u64::from(limbs) * u64::from(self.per_limb)
+    }
+}
+
+/// The cost of hashing `length` bytes, which is `base + length * per_byte`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HashCost {
+    pub base: u32,
+    pub per_byte: u32,
+}
+
+impl HashCost {
+    pub fn cost(&self, length: u32) -> u64 {
+        u64::from(self.base) + u64::from(length) * u64::from(self.per_byte)
+    }
+}
+
+/// The cost of verifying a signature against `count` public keys, which is
+/// `base + count * per_pubkey`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AggregateCost {
+    pub base: u32,
+    pub per_pubkey: u32,
+}
+
+impl AggregateCost {
+    pub fn cost(&self, count: u32) -> u64 {
+        u64::from(self.base) + u64::from(count) * u64::from(self.per_pubkey)
+    }
+}
+
+/// The cost of a pairing check over `count` pairs, which is `base + count * per_pair`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PairingCost {
+    pub base: u32,
+    pub per_pair: u32,
+}
+
+impl PairingCost {
+    pub fn cost(&self, count: u32) -> u64 {
+        u64::from(self.base) + u64::from(count) * u64::from(self.per_pair)
+    }
+}
+
+/// The cost of merkleizing `chunks` chunks, which is `base + chunks * per_chunk`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MerkleCost {
+    pub base: u32,
+    pub per_chunk: u32,
+}
+
+impl MerkleCost {
+    pub fn cost(&self, chunks: u64) -> u64 {
+        u64::from(self.base) + chunks * u64::from(self.per_chunk)
+    }
+}
+
+impl Default for TickCosts {
+    fn default() -> Self {
+        TickCosts {
+            enabled: true,
+            regular: 1,
+            grow_memory: 0,
+            instructions: BTreeMap::new(),
+            storage_load: 200,
+            storage_store: 5_000,
+            bignum: 10,
+            montgomery_add: FieldCost {
+                base: 5,
+                per_limb: 1,
+            },
+            montgomery_mul: FieldCost {
+                base: 10,
+                per_limb: 8,
+            },
+            keccak256: HashCost {
+                base: 30,
+                per_byte: 1,
+            },
+            sha256: HashCost {
+                base: 60,
+                per_byte: 2,
+            },
+            bls_verify: 200_000,
+            bls_aggregate_verify: AggregateCost {
+                base: 100_000,
+                per_pubkey: 100_000,
+            },
+            bls_fast_aggregate_verify: AggregateCost {
+                base: 200_000,
+                per_pubkey: 1_000,
+            },
+            ecrecover: 3_000,
+            bn128_add: 150,
+            bn128_mul: 6_000,
+            bn128_pairing: PairingCost {
+                base: 45_000,
+                per_pair: 34_000,
+            },
+            ssz_hash_tree_root: MerkleCost {
+                base: 100,
+                per_chunk: 200,
+            },
+            ssz_verify_multiproof: MerkleCost {
+                base: 100,
+                per_chunk: 400,
+            },
+        }
+    }
+}
+
+impl TickCosts {
+    /// Costs which do not inject any metering into the code.
+    pub fn disabled() -> Self {
+        TickCosts {
+            enabled: false,
+            ..Default::default()
+        }
+    }
+
+    fn rules(&self) -> Result<Set, ScoutError> {
+        let mut entries = BTreeMap::new();
+        for (name, cost) in &self.instructions {
+            let instruction_type: InstructionType = name.parse().map_err(|_| {
+                ScoutError::InvalidInput(format!("Unknown instruction class: {}", name))
+            })?;
+            entries.insert(instruction_type, Metering::Fixed(*cost));
+        }
+        Ok(Set::new(self.regular, entries).with_grow_cost(self.grow_memory))
+    }
+}
+
+/// Injects calls to `env.gas` charging the configured costs into every function of the module.
+pub(crate) fn inject_metering(module: Module, costs: &TickCosts) -> Result<Module, ScoutError> {
+    if !costs.enabled {
+        return Ok(module);
+    }
+    pwasm_utils::inject_gas_counter(module, &costs.rules()?).map_err(|_| {
+        ScoutError::Validation("Module contains instructions forbidden by metering".to_string())
+    })
+}
@@ -0,0 +1,301 @@
+//! Montgomery arithmetic over odd moduli of up to 384 bits, backing the field arithmetic host
+//! functions of the `bignum` namespace.
+//!
+//! Numbers are passed to and from wasm memory as big-endian byte strings and are kept internally
+//! as little-endian 64-bit limbs. Montgomery form uses R = 2^(64 * limbs).
+
+/// The field identifiers accepted by the `bignum.field*` host functions.
+pub const BN128_FQ: u32 = 0;
+pub const BN128_FR: u32 = 1;
+pub const BLS12_381_FQ: u32 = 2;
+pub const BLS12_381_FR: u32 = 3;
+
+const BN128_FQ_MODULUS: [u64; 4] = [
+    0x3c20_8c16_d87c_fd47,
+    0x9781_6a91_6871_ca8d,
+    0xb850_45b6_8181_585d,
+    0x3064_4e72_e131_a029,
+];
+const BN128_FR_MODULUS: [u64; 4] = [
+    0x43e1_f593_f000_0001,
+    0x2833_e848_79b9_7091,
+    0xb850_45b6_8181_585d,
+    0x3064_4e72_e131_a029,
+];
+const BLS12_381_FQ_MODULUS: [u64; 6] = [
+    0xb9fe_ffff_ffff_aaab,
+    0x1eab_fffe_b153_ffff,
+    0x6730_d2a0_f6b0_f624,
+    0x6477_4b84_f385_12bf,
+    0x4b1b_a7b6_434b_acd7,
+    0x1a01_11ea_397f_e69a,
+];
+const BLS12_381_FR_MODULUS: [u64; 4] = [
+    0xffff_ffff_0000_0001,
+    0x53bd_a402_fffe_5bfe,
+    0x3339_d808_09a1_d805,
+    0x73ed_a753_299d_7d48,
+];
+
+pub struct Modulus {
+    limbs: Vec<u64>,
+    /// -modulus^-1 mod 2^64
+    inv: u64,
+}
+
+impl Modulus {
+    fn new(limbs: Vec<u64>) -> Result<Self, &'static str> {
+        if limbs[0] & 1 == 0 {
+            return Err("modulus must be odd");
+        }
+        // Newton's iteration doubles the number of correct low bits each step.
+        let mut inv = 1u64;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(limbs[0].wrapping_mul(inv)));
+        }
+        Ok(Modulus {
+            limbs,
+            inv: inv.wrapping_neg(),
+        })
+    }
+
+    /// Parses a big-endian modulus, whose length must be a multiple of 8 bytes.
+    pub fn from_be_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        Modulus::new(limbs_from_be_bytes(bytes))
+    }
+
+    pub fn preset(field: u32) -> Option<Self> {
+        let limbs = match field {
+            BN128_FQ => BN128_FQ_MODULUS.to_vec(),
+            BN128_FR => BN128_FR_MODULUS.to_vec(),
+            BLS12_381_FQ => BLS12_381_FQ_MODULUS.to_vec(),
+            BLS12_381_FR => BLS12_381_FR_MODULUS.to_vec(),
+            _ => return None,
+        };
+        Modulus::new(limbs).ok()
+    }
+
+    /// The size of the modulus and of the field elements in bytes.
+    pub fn size(&self) -> usize {
+        self.limbs.len() * 8
+    }
+
+    /// Parses a big-endian field element, which must be reduced modulo the modulus.
+    pub fn element(&self, bytes: &[u8]) -> Result<Vec<u64>, &'static str> {
+        let limbs = limbs_from_be_bytes(bytes);
+        if !less_than(&limbs, &self.limbs) {
+            return Err("operand is not reduced modulo the modulus");
+        }
+        Ok(limbs)
+    }
+
+    pub fn add(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let (mut sum, carry) = add_limbs(a, b);
+        if carry || !less_than(&sum, &self.limbs) {
+            sum = sub_limbs(&sum, &self.limbs).0;
+        }
+        sum
+    }
+
+    pub fn sub(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let (difference, borrow) = sub_limbs(a, b);
+        if borrow {
+            add_limbs(&difference, &self.limbs).0
+        } else {
+            difference
+        }
+    }
+
+    /// Computes a * b * R^-1 mod modulus (CIOS method).
+    pub fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let n = self.limbs.len();
+        let mut t = vec![0u64; n + 2];
+        for b_limb in b {
+            let mut carry = 0u128;
+            for j in 0..n {
+                let v = u128::from(t[j]) + u128::from(a[j]) * u128::from(*b_limb) + carry;
+                t[j] = v as u64;
+                carry = v >> 64;
+            }
+            let v = u128::from(t[n]) + carry;
+            t[n] = v as u64;
+            t[n + 1] = (v >> 64) as u64;
+
+            let m = t[0].wrapping_mul(self.inv);
+            let v = u128::from(t[0]) + u128::from(m) * u128::from(self.limbs[0]);
+            let mut carry = v >> 64;
+            for j in 1..n {
+                let v = u128::from(t[j]) + u128::from(m) * u128::from(self.limbs[j]) + carry;
+                t[j - 1] = v as u64;
+                carry = v >> 64;
+            }
+            let v = u128::from(t[n]) + carry;
+            t[n - 1] = v as u64;
+            t[n] = t[n + 1] + (v >> 64) as u64;
+        }
+        let overflow = t[n] != 0;
+        t.truncate(n);
+        if overflow || !less_than(&t, &self.limbs) {
+            t = sub_limbs(&t, &self.limbs).0;
+        }
+        t
+    }
+
+    /// Converts a into Montgomery form, i.e. computes a * R mod modulus.
+    pub fn to_montgomery(&self, a: &[u64]) -> Vec<u64> {
+        // R^2 mod modulus by doubling 1 modulo the modulus 2 * log2(R) times.
+        let mut r2 = vec![0u64; self.limbs.len()];
+        r2[0] = 1;
+        for _ in 0..(2 * 64 * self.limbs.len()) {
+            r2 = self.add(&r2, &r2);
+        }
+        self.mul(a, &r2)
+    }
+
+    /// Converts a out of Montgomery form, i.e. computes a * R^-1 mod modulus.
+    pub fn from_montgomery(&self, a: &[u64]) -> Vec<u64> {
+        let mut one = vec![0u64; self.limbs.len()];
+        one[0] = 1;
+        self.mul(a, &one)
+    }
+}
+
+fn limbs_from_be_bytes(bytes: &[u8]) -> Vec<u64> {
+    bytes
+        .rchunks(8)
+        .map(|chunk| {
+            let mut raw = [0u8; 8];
+            raw.copy_from_slice(chunk);
+            u64::from_be_bytes(raw)
+        })
+        .collect()
+}
+
+pub fn limbs_to_be_bytes(limbs: &[u64]) -> Vec<u8> {
+    limbs
+        .iter()
+        .rev()
+        .flat_map(|limb| limb.to_be_bytes().to_vec())
+        .collect()
+}
+
+fn less_than(a: &[u64], b: &[u64]) -> bool {
+    for (x, y) in a.iter().rev().zip(b.iter().rev()) {
+        if x != y {
+            return x < y;
+        }
+    }
+    false
+}
+
+fn add_limbs(a: &[u64], b: &[u64]) -> (Vec<u64>, bool) {
+    let mut carry = false;
+    let sum = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| {
+            let (v, c1) = x.overflowing_add(*y);
+            let (v, c2) = v.overflowing_add(carry as u64);
+            carry = c1 || c2;
+            v
+        })
+        .collect();
+    (sum, carry)
+}
+
+fn sub_limbs(a: &[u64], b: &[u64]) -> (Vec<u64>, bool) {
+    let mut borrow = false;
+    let difference = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| {
+            let (v, b1) = x.overflowing_sub(*y);
+            let (v, b2) = v.overflowing_sub(borrow as u64);
+            borrow = b1 || b2;
+            v
+        })
+        .collect();
+    (difference, borrow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use primitive_types::{U256, U512};
+    use std::convert::TryFrom;
+
+    fn to_u256(limbs: &[u64]) -> U256 {
+        U256::from_big_endian(&limbs_to_be_bytes(limbs))
+    }
+
+    fn from_u256(value: U256) -> Vec<u64> {
+        let mut raw = [0u8; 32];
+        value.to_big_endian(&mut raw);
+        limbs_from_be_bytes(&raw)
+    }
+
+    fn mulmod(a: U256, b: U256, m: U256) -> U256 {
+        U256::try_from(a.full_mul(b) % U512::from(m)).unwrap()
+    }
+
+    #[test]
+    fn inverse() {
+        for field in &[BN128_FQ, BN128_FR, BLS12_381_FQ, BLS12_381_FR] {
+            let modulus = Modulus::preset(*field).unwrap();
+            assert_eq!(modulus.limbs[0].wrapping_mul(modulus.inv), u64::MAX);
+        }
+    }
+
+    #[test]
+    fn montgomery_multiplication_matches_mulmod() {
+        for field in &[BN128_FQ, BN128_FR, BLS12_381_FR] {
+            let modulus = Modulus::preset(*field).unwrap();
+            let m = to_u256(&modulus.limbs);
+            let values = [U256::zero(), U256::one(), 12345.into(), m / 3, m - 1];
+            for a in &values {
+                for b in &values {
+                    let a_mont = modulus.to_montgomery(&from_u256(*a));
+                    let b_mont = modulus.to_montgomery(&from_u256(*b));
+                    let product = modulus.from_montgomery(&modulus.mul(&a_mont, &b_mont));
+                    assert_eq!(to_u256(&product), mulmod(*a, *b, m));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bls12_381_fq_identities() {
+        let modulus = Modulus::preset(BLS12_381_FQ).unwrap();
+        let one = modulus.to_montgomery(&[1, 0, 0, 0, 0, 0]);
+        let minus_one = modulus.sub(&[0; 6], &one);
+        // (-1) * (-1) = 1
+        assert_eq!(modulus.mul(&minus_one, &minus_one), one);
+        // a + a = 2 * a
+        let a = modulus.to_montgomery(&[7, 6, 5, 4, 3, 2]);
+        let two = modulus.add(&one, &one);
+        assert_eq!(modulus.add(&a, &a), modulus.mul(&a, &two));
+        assert_eq!(
+            modulus.from_montgomery(&modulus.to_montgomery(&[7, 6, 5, 4, 3, 2])),
+            vec![7, 6, 5, 4, 3, 2]
+        );
+    }
+
+    #[test]
+    fn add_and_sub_wrap_around() {
+        let modulus = Modulus::preset(BN128_FR).unwrap();
+        let m = to_u256(&modulus.limbs);
+        let a = from_u256(m - 1);
+        let b = from_u256(5.into());
+        assert_eq!(to_u256(&modulus.add(&a, &b)), 4.into());
+        assert_eq!(to_u256(&modulus.sub(&b, &a)), 6.into());
+    }
+
+    #[test]
+    fn rejects_even_modulus_and_unreduced_operands() {
+        assert!(Modulus::from_be_bytes(&[0u8; 32]).is_err());
+        let modulus = Modulus::preset(BN128_FQ).unwrap();
+        assert!(modulus
+            .element(&limbs_to_be_bytes(&BN128_FQ_MODULUS))
+            .is_err());
+    }
+}
@@ -0,0 +1,263 @@
+//! Machine-readable output of test results for CI systems: JUnit XML and TAP.
+//!
+//! Each test file becomes a suite with a test case for every block, followed by one for each of
+//! the checks of the whole file. If a test file could not be run to the end, the block it stopped
+//! at is an erroring case, or a single erroring case stands in if it could not be run at all.
+
+use crate::report::TestReport;
+use crate::runner::Summary;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// The formats in which results can be printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+    /// JUnit XML.
+    Junit,
+    /// The Test Anything Protocol, version 13.
+    Tap,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "junit" => Ok(OutputFormat::Junit),
+            "tap" => Ok(OutputFormat::Tap),
+            _ => Err(format!("Unknown output format {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Status {
+    Passed,
+    /// A mismatch against the expectations, with one message per mismatch.
+    Failed(Vec<String>),
+    /// A block which failed but was tolerated.
+    Skipped(String),
+    /// The test file could not be run.
+    Error(String),
+}
+
+struct Case {
+    name: String,
+    status: Status,
+}
+
+fn cases(report: &TestReport) -> Vec<Case> {
+    let mut cases = Vec::new();
+    for (i, block) in report.blocks.iter().enumerate() {
+        let name = format!("block {}", block.index);
+        let check = report.checks.iter().find(|check| check.name == name);
+        // A test file which could not be run further was stopped at its last block.
+        let aborted = match &report.error {
+            Some(error) if i + 1 == report.blocks.len() => Some(error),
+            _ => None,
+        };
+        let status = match (aborted, check, &block.error, block.outcome) {
+            (Some(error), _, _, _) => Status::Error(error.clone()),
+            (None, Some(check), _, _) if !check.passed => Status::Failed(check.mismatches.clone()),
+            (None, None, Some(error), Some(outcome)) => {
+                Status::Skipped(format!("{}: {}", outcome, error))
+            }
+            _ => Status::Passed,
+        };
+        cases.push(Case { name, status });
+    }
+    if let Some(error) = &report.error {
+        if report.blocks.is_empty() {
+            cases.push(Case {
+                name: "run".to_string(),
+                status: Status::Error(error.clone()),
+            });
+        }
+        return cases;
+    }
+    for check in &report.checks {
+        if check.name.starts_with("block ") {
+            continue;
+        }
+        let status = if check.passed {
+            Status::Passed
+        } else {
+            Status::Failed(check.mismatches.clone())
+        };
+        cases.push(Case {
+            name: check.name.clone(),
+            status,
+        });
+    }
+    cases
+}
+
+fn is_failure(status: &Status) -> bool {
+    matches!(status, Status::Failed(_))
+}
+
+fn is_error(status: &Status) -> bool {
+    matches!(status, Status::Error(_))
+}
+
+fn is_skipped(status: &Status) -> bool {
+    matches!(status, Status::Skipped(_))
+}
+
+fn count(cases: &[Case], f: fn(&Status) -> bool) -> usize {
+    cases.iter().filter(|case| f(&case.status)).count()
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Formats the results as a JUnit XML document.
+pub fn junit(summary: &Summary) -> String {
+    let suites: Vec<(&TestReport, Vec<Case>)> = summary
+        .tests
+        .iter()
+        .map(|report| (report, cases(report)))
+        .collect();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let total = |f: fn(&Status) -> bool| {
+        suites
+            .iter()
+            .map(|(_, cases)| count(cases, f))
+            .sum::<usize>()
+    };
+    writeln!(
+        xml,
+        "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">",
+        total(|_| true),
+        total(is_failure),
+        total(is_error),
+        summary.seconds
+    )
+    .unwrap();
+    for (report, cases) in &suites {
+        let name = escape_xml(&report.file);
+        writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+            name,
+            cases.len(),
+            count(cases, is_failure),
+            count(cases, is_error),
+            count(cases, is_skipped),
+            report.seconds
+        )
+        .unwrap();
+        for case in cases {
+            let open = format!(
+                "    <testcase name=\"{}\" classname=\"{}\"",
+                escape_xml(&case.name),
+                name
+            );
+            match &case.status {
+                Status::Passed => writeln!(xml, "{}/>", open).unwrap(),
+                Status::Failed(mismatches) => {
+                    let message = escape_xml(&mismatches.join("\n"));
+                    writeln!(xml, "{}>", open).unwrap();
+                    writeln!(
+                        xml,
+                        "      <failure message=\"{}\">{}</failure>",
+                        message, message
+                    )
+                    .unwrap();
+                    writeln!(xml, "    </testcase>").unwrap();
+                }
+                Status::Skipped(message) => {
+                    writeln!(xml, "{}>", open).unwrap();
+                    writeln!(xml, "      <skipped message=\"{}\"/>", escape_xml(message)).unwrap();
+                    writeln!(xml, "    </testcase>").unwrap();
+                }
+                Status::Error(message) => {
+                    let message = escape_xml(message);
+                    writeln!(xml, "{}>", open).unwrap();
+                    writeln!(
+                        xml,
+                        "      <error message=\"{}\">{}</error>",
+                        message, message
+                    )
+                    .unwrap();
+                    writeln!(xml, "    </testcase>").unwrap();
+                }
+            }
+        }
+        writeln!(xml, "  </testsuite>").unwrap();
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Writes `messages` as the YAML diagnostic block of a TAP test point.
+fn tap_diagnostic(tap: &mut String, indent: &str, severity: &str, messages: &[String]) {
+    writeln!(tap, "{}  ---", indent).unwrap();
+    writeln!(tap, "{}  severity: {}", indent, severity).unwrap();
+    writeln!(tap, "{}  messages:", indent).unwrap();
+    for message in messages {
+        // Debug formatting quotes and escapes the message as a valid YAML string.
+        writeln!(tap, "{}    - {:?}", indent, message).unwrap();
+    }
+    writeln!(tap, "{}  ...", indent).unwrap();
+}
+
+/// Formats the results as a TAP version 13 stream, with the cases of each test file as a subtest.
+pub fn tap(summary: &Summary) -> String {
+    let mut tap = String::from("TAP version 13\n");
+    writeln!(tap, "1..{}", summary.tests.len()).unwrap();
+    for (i, report) in summary.tests.iter().enumerate() {
+        let cases = cases(report);
+        writeln!(tap, "# Subtest: {}", report.file).unwrap();
+        writeln!(tap, "    1..{}", cases.len()).unwrap();
+        for (j, case) in cases.iter().enumerate() {
+            match &case.status {
+                Status::Passed => writeln!(tap, "    ok {} - {}", j + 1, case.name).unwrap(),
+                Status::Skipped(message) => writeln!(
+                    tap,
+                    "    ok {} - {} # SKIP {}",
+                    j + 1,
+                    case.name,
+                    message.replace('\n', " ")
+                )
+                .unwrap(),
+                Status::Failed(mismatches) => {
+                    writeln!(tap, "    not ok {} - {}", j + 1, case.name).unwrap();
+                    tap_diagnostic(&mut tap, "    ", "fail", mismatches);
+                }
+                Status::Error(message) => {
+                    writeln!(tap, "    not ok {} - {}", j + 1, case.name).unwrap();
+                    tap_diagnostic(&mut tap, "    ", "error", std::slice::from_ref(message));
+                }
+            }
+        }
+        let result = if report.passed() { "ok" } else { "not ok" };
+        writeln!(
+            tap,
+            "{} {} - {} # time={:.3}s",
+            result,
+            i + 1,
+            report.file,
+            report.seconds
+        )
+        .unwrap();
+    }
+    tap
+}
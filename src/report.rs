@@ -0,0 +1,180 @@
+//! The results of running test files, printable for humans and serializable as a JSON document.
+
+use crate::testfile::BlockOutcome;
+use crate::{ScoutError, ShardBlockResult, ShardState};
+use rustc_hex::ToHex;
+use serde::Serialize;
+use std::fmt;
+
+/// The result of running a test file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TestReport {
+    pub file: String,
+    /// The error which stopped the test file from being run, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub blocks: Vec<BlockReport>,
+    pub checks: Vec<CheckResult>,
+    /// The time taken to run the test file, if measured.
+    pub seconds: f64,
+}
+
+/// The result of processing a single block.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockReport {
+    pub index: usize,
+    pub env: usize,
+    /// How processing the block ended, or none if it could not be processed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<BlockOutcome>,
+    /// The error of a failed block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The ticks used by a successful block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ticks_used: Option<u32>,
+    /// The ticks left by a successful block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ticks_left: Option<u32>,
+    pub storage_loads: u32,
+    pub storage_stores: u32,
+    /// The state root of the block's execution environment after the block, if it exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_state: Option<String>,
+}
+
+/// The result of comparing part of the outcome against the expectation of the test file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mismatches: Vec<String>,
+}
+
+impl TestReport {
+    pub fn new(file: &str) -> Self {
+        TestReport {
+            file: file.to_string(),
+            error: None,
+            blocks: Vec::new(),
+            checks: Vec::new(),
+            seconds: 0.0,
+        }
+    }
+
+    /// A report for a test file which could not be run.
+    pub fn from_error(file: &str, error: &ScoutError) -> Self {
+        TestReport {
+            error: Some(error.to_string()),
+            ..TestReport::new(file)
+        }
+    }
+
+    /// Records the error which stopped the test file from being run further, also on the block
+    /// being processed if it was stopped by one.
+    pub(crate) fn abort(mut self, error: &ScoutError) -> Self {
+        let message = error.to_string();
+        if let Some(block) = self.blocks.last_mut() {
+            block.error.get_or_insert_with(|| message.clone());
+        }
+        self.error = Some(message);
+        self
+    }
+
+    /// Whether the test file was run and all checks passed.
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.checks.iter().all(|check| check.passed)
+    }
+}
+
+impl BlockReport {
+    pub(crate) fn new(
+        index: usize,
+        env: usize,
+        outcome: Option<BlockOutcome>,
+        result: &Result<ShardBlockResult, ScoutError>,
+        shard_state: &ShardState,
+    ) -> Self {
+        let post_state = shard_state
+            .exec_env_states
+            .get(env)
+            .map(|state| state.bytes.to_hex());
+        match result {
+            Ok(result) => BlockReport {
+                index,
+                env,
+                outcome,
+                error: None,
+                ticks_used: Some(result.ticks_used),
+                ticks_left: Some(result.ticks_left),
+                storage_loads: result.storage_accesses.loads,
+                storage_stores: result.storage_accesses.stores,
+                post_state,
+            },
+            Err(err) => BlockReport {
+                index,
+                env,
+                outcome,
+                error: Some(err.to_string()),
+                ticks_used: None,
+                ticks_left: None,
+                storage_loads: 0,
+                storage_stores: 0,
+                post_state,
+            },
+        }
+    }
+}
+
+impl CheckResult {
+    pub fn new(name: impl Into<String>, mismatches: Vec<String>) -> Self {
+        CheckResult {
+            name: name.into(),
+            passed: mismatches.is_empty(),
+            mismatches,
+        }
+    }
+}
+
+impl fmt::Display for TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for block in &self.blocks {
+            writeln!(f, "{}", block)?;
+        }
+        for check in &self.checks {
+            writeln!(f, "{}", check)?;
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "Unexpected test failure: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for BlockReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.error, self.ticks_used, self.ticks_left) {
+            (Some(error), _, _) => write!(f, "Block {} failed: {}", self.index, error),
+            (None, Some(ticks_used), Some(ticks_left)) => write!(
+                f,
+                "Block {} used {} ticks ({} left), {} storage loads and {} storage stores.",
+                self.index, ticks_used, ticks_left, self.storage_loads, self.storage_stores
+            ),
+            _ => write!(f, "Block {} succeeded.", self.index),
+        }
+    }
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.passed {
+            return write!(f, "Matching {}.", self.name);
+        }
+        write!(f, "Mismatching {}:", self.name)?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n  {}", mismatch)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+//! Running many test files in one go.
+
+use crate::report::TestReport;
+use crate::testfile::{process_yaml_test, TestOptions};
+use crate::ScoutError;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Whether `path` looks like a test file when searching directories. JSON files are only taken
+/// to be test files if they are objects with a `beacon_state`, so that other JSON files such as
+/// reports can live alongside the test files.
+fn is_test_file(path: &Path) -> bool {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("yaml") | Some("yml") => true,
+        Some("json") => fs::read(path)
+            .ok()
+            .and_then(|content| serde_json::from_slice::<serde_json::Value>(&content).ok())
+            .is_some_and(|json| json.get("beacon_state").is_some()),
+        _ => false,
+    }
+}
+
+fn collect_dir(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), ScoutError> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_dir(&path, files)?;
+        } else if is_test_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Expands test files, directories and glob patterns into the list of test files to run.
+///
+/// Directories are searched recursively for `.yaml`, `.yml` and `.json` test files, in sorted
+/// order. A pattern which matches nothing is an error.
+pub fn collect_tests(patterns: &[String]) -> Result<Vec<PathBuf>, ScoutError> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        let path = Path::new(pattern);
+        if path.is_dir() {
+            collect_dir(path, &mut files)?;
+        } else if path.exists() {
+            files.push(path.to_path_buf());
+        } else {
+            let matches = glob::glob(pattern)
+                .map_err(|err| {
+                    ScoutError::InvalidInput(format!("Invalid pattern {}: {}", pattern, err))
+                })?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| ScoutError::InvalidInput(err.to_string()))?;
+            if matches.is_empty() {
+                return Err(ScoutError::InvalidInput(format!(
+                    "No test files found for {}",
+                    pattern
+                )));
+            }
+            for path in matches {
+                if path.is_dir() {
+                    collect_dir(&path, &mut files)?;
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn run_test(file: &Path, options: &TestOptions) -> TestReport {
+    let filename = file.to_string_lossy();
+    let start = Instant::now();
+    let mut report = match process_yaml_test(&filename, options) {
+        Ok(report) => report,
+        Err(err) => TestReport::from_error(&filename, &err),
+    };
+    report.seconds = start.elapsed().as_secs_f64();
+    report
+}
+
+/// Runs each of the test files, in parallel if requested. The reports are in the order of `files`.
+pub fn run_tests(files: &[PathBuf], options: &TestOptions, parallel: bool) -> Summary {
+    let start = Instant::now();
+    let tests = if parallel {
+        files
+            .par_iter()
+            .map(|file| run_test(file, options))
+            .collect()
+    } else {
+        files.iter().map(|file| run_test(file, options)).collect()
+    };
+    Summary {
+        tests,
+        seconds: start.elapsed().as_secs_f64(),
+    }
+}
+
+/// The results of running a batch of test files.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    pub tests: Vec<TestReport>,
+    /// The wall-clock time taken by the whole batch.
+    pub seconds: f64,
+}
+
+impl Summary {
+    /// The number of test files which ran and whose checks all passed.
+    pub fn passed(&self) -> usize {
+        self.tests.iter().filter(|test| test.passed()).count()
+    }
+
+    /// The number of test files which ran but had mismatches.
+    pub fn failed(&self) -> usize {
+        self.tests
+            .iter()
+            .filter(|test| test.error.is_none() && !test.passed())
+            .count()
+    }
+
+    /// The number of test files which could not be run.
+    pub fn errors(&self) -> usize {
+        self.tests
+            .iter()
+            .filter(|test| test.error.is_some())
+            .count()
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for test in &self.tests {
+            let status = if test.passed() {
+                "PASS"
+            } else if test.error.is_some() {
+                "ERROR"
+            } else {
+                "FAIL"
+            };
+            writeln!(f, "{:5} {} ({:.3}s)", status, test.file, test.seconds)?;
+        }
+        writeln!(
+            f,
+            "{} passed, {} failed, {} errors in {:.3}s",
+            self.passed(),
+            self.failed(),
+            self.errors(),
+            self.seconds
+        )
+    }
+}
@@ -0,0 +1,204 @@
+//! SSZ merkleization backing the `ssz` host functions.
+//!
+//! Nodes are 32-byte chunks hashed with SHA-256. Generalized indices number the nodes of a tree
+//! with the root being 1 and the children of node `i` being `2 * i` and `2 * i + 1`.
+
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub const CHUNK_SIZE: usize = 32;
+
+pub type Chunk = [u8; CHUNK_SIZE];
+
+fn hash_pair(left: &Chunk, right: &Chunk) -> Chunk {
+    let mut hasher = Sha256::new();
+    hasher.input(left);
+    hasher.input(right);
+    let mut chunk = [0u8; CHUNK_SIZE];
+    chunk.copy_from_slice(&hasher.result());
+    chunk
+}
+
+/// Packs `data` into chunks, padding the last one with zeroes, and merkleizes them, padding the
+/// number of chunks with zero chunks to the next power of two.
+pub fn hash_tree_root(data: &[u8]) -> Chunk {
+    let mut layer: Vec<Chunk> = data
+        .chunks(CHUNK_SIZE)
+        .map(|bytes| {
+            let mut chunk = [0u8; CHUNK_SIZE];
+            chunk[..bytes.len()].copy_from_slice(bytes);
+            chunk
+        })
+        .collect();
+    if layer.is_empty() {
+        return [0u8; CHUNK_SIZE];
+    }
+    // The root of a subtree of zero chunks at the height of the current layer.
+    let mut zero = [0u8; CHUNK_SIZE];
+    while layer.len() > 1 {
+        if layer.len() % 2 == 1 {
+            layer.push(zero);
+        }
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+        zero = hash_pair(&zero, &zero);
+    }
+    layer[0]
+}
+
+/// The indices of the nodes needed to compute the root from the leaves at `indices`, in
+/// decreasing order, which is the order proofs list them in.
+fn helper_indices(indices: &[u64]) -> Vec<u64> {
+    let mut helpers = BTreeSet::new();
+    let mut paths = BTreeSet::new();
+    for index in indices {
+        let mut node = *index;
+        while node > 1 {
+            helpers.insert(node ^ 1);
+            paths.insert(node);
+            node /= 2;
+        }
+    }
+    let mut helpers: Vec<u64> = helpers.difference(&paths).cloned().collect();
+    helpers.reverse();
+    helpers
+}
+
+fn has_leaf_ancestor(leaves: &BTreeMap<u64, Chunk>, index: u64) -> bool {
+    let mut node = index / 2;
+    while node >= 1 {
+        if leaves.contains_key(&node) {
+            return true;
+        }
+        node /= 2;
+    }
+    false
+}
+
+/// Verifies an SSZ Merkle multiproof of the `leaves` at the generalized `indices` against `root`,
+/// with `proof` holding the helper nodes in decreasing order of their generalized index.
+pub fn verify_multiproof(root: &Chunk, indices: &[u64], leaves: &[Chunk], proof: &[Chunk]) -> bool {
+    if indices.is_empty() || indices.len() != leaves.len() || indices.contains(&0) {
+        return false;
+    }
+    let helpers = helper_indices(indices);
+    if helpers.len() != proof.len() {
+        return false;
+    }
+    let mut nodes: BTreeMap<u64, Chunk> = indices
+        .iter()
+        .cloned()
+        .zip(leaves.iter().cloned())
+        .collect();
+    // Duplicate leaves, or leaves which are ancestors of other leaves, would go unchecked.
+    if nodes.len() != indices.len()
+        || indices
+            .iter()
+            .any(|index| has_leaf_ancestor(&nodes, *index))
+    {
+        return false;
+    }
+    nodes.extend(helpers.into_iter().zip(proof.iter().cloned()));
+
+    // Hash the known nodes together from the deepest upwards until reaching the root.
+    let mut pending: Vec<u64> = nodes.keys().rev().cloned().collect();
+    let mut position = 0;
+    while position < pending.len() {
+        let index = pending[position];
+        let parent = index / 2;
+        if index > 1 && !nodes.contains_key(&parent) {
+            if let (Some(left), Some(right)) = (nodes.get(&(index & !1)), nodes.get(&(index | 1))) {
+                let node = hash_pair(left, right);
+                nodes.insert(parent, node);
+                pending.push(parent);
+            }
+        }
+        position += 1;
+    }
+    nodes.get(&1) == Some(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(byte: u8) -> Chunk {
+        [byte; CHUNK_SIZE]
+    }
+
+    #[test]
+    fn root_of_a_single_chunk_is_the_chunk() {
+        assert_eq!(hash_tree_root(&[]), chunk(0));
+        let mut padded = chunk(0);
+        padded[0..3].copy_from_slice(b"abc");
+        assert_eq!(hash_tree_root(b"abc"), padded);
+    }
+
+    #[test]
+    fn chunks_are_padded_to_a_power_of_two() {
+        let data: Vec<u8> = [chunk(1), chunk(2), chunk(3)].concat();
+        let expected = hash_pair(
+            &hash_pair(&chunk(1), &chunk(2)),
+            &hash_pair(&chunk(3), &chunk(0)),
+        );
+        assert_eq!(hash_tree_root(&data), expected);
+    }
+
+    #[test]
+    fn multiproof() {
+        // A tree with the leaves 1, 2, 3 and 4 at the generalized indices 4 to 7.
+        let left = hash_pair(&chunk(1), &chunk(2));
+        let right = hash_pair(&chunk(3), &chunk(4));
+        let root = hash_pair(&left, &right);
+
+        assert!(verify_multiproof(&root, &[1], &[root], &[]));
+        assert!(verify_multiproof(
+            &root,
+            &[4],
+            &[chunk(1)],
+            &[chunk(2), right]
+        ));
+        assert!(verify_multiproof(
+            &root,
+            &[7, 4],
+            &[chunk(4), chunk(1)],
+            &[chunk(3), chunk(2)]
+        ));
+        assert!(verify_multiproof(
+            &root,
+            &[2, 7],
+            &[left, chunk(4)],
+            &[chunk(3)]
+        ));
+
+        assert!(!verify_multiproof(
+            &root,
+            &[4],
+            &[chunk(1)],
+            &[right, chunk(2)]
+        ));
+        assert!(!verify_multiproof(
+            &root,
+            &[4],
+            &[chunk(2)],
+            &[chunk(2), right]
+        ));
+        assert!(!verify_multiproof(&root, &[4], &[chunk(1)], &[right]));
+        assert!(!verify_multiproof(&root, &[0], &[chunk(1)], &[]));
+        assert!(!verify_multiproof(&root, &[], &[], &[]));
+        assert!(!verify_multiproof(
+            &root,
+            &[4, 4],
+            &[chunk(1), chunk(9)],
+            &[chunk(2), right]
+        ));
+        assert!(!verify_multiproof(
+            &root,
+            &[2, 4],
+            &[left, chunk(9)],
+            &[chunk(2), right]
+        ));
+    }
+}
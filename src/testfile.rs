@@ -0,0 +1,908 @@
+use crate::report::{BlockReport, CheckResult, TestReport};
+use crate::types::*;
+use crate::{
+    process_shard_block, BLSPubKey, BLSSignature, BeaconState, Deposit, ExecutionScript, Hash,
+    Library, ScoutError, ShardBlock, ShardBlockBody, ShardBlockHeader, ShardBlockResult,
+    ShardState, Storage, TickCosts, DEFAULT_TICKS,
+};
+use primitive_types::U256;
+use rustc_hex::{FromHex, ToHex};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The code of a script or library: a path to a wasm file, or inline bytecode or text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestCode {
+    File(String),
+    /// Hex encoded wasm bytecode.
+    Hex(String),
+    /// Base64 encoded wasm bytecode.
+    Base64(String),
+    /// WebAssembly text format.
+    Wat(String),
+}
+
+/// An execution script, given either as a path or as a map with one of the keys of `TestCode`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TestScript {
+    File(String),
+    Code(TestCode),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestLibrary {
+    pub name: String,
+    #[serde(flatten)]
+    pub code: TestCode,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestBeaconState {
+    pub execution_scripts: Vec<TestScript>,
+    pub libraries: Option<Vec<TestLibrary>>,
+}
+
+/// A field of an SSZ container, encoded in little-endian order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestSszField {
+    Bool(bool),
+    Uint8(u8),
+    Uint16(u16),
+    Uint32(u32),
+    Uint64(u64),
+    /// A decimal number, or a hex number prefixed with `0x`.
+    Uint256(String),
+    /// A fixed-length byte vector, in hex.
+    Vector(String),
+    /// A variable-length byte list, in hex.
+    List(String),
+}
+
+/// The data of a block: hex, a path to a binary file, or an SSZ container.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TestBlockData {
+    Hex(String),
+    File { file: String },
+    Ssz { ssz: Vec<TestSszField> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestShardBlock {
+    pub env: u64,
+    pub data: TestBlockData,
+    /// Overrides the tick budget for this block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticks: Option<u32>,
+    /// The expected outcome of processing this block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<TestBlockExpectation>,
+}
+
+/// How processing a block ended.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockOutcome {
+    #[default]
+    Success,
+    /// The script trapped or a host function it called failed.
+    Trap,
+    OutOfTicks,
+}
+
+impl fmt::Display for BlockOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockOutcome::Success => write!(f, "success"),
+            BlockOutcome::Trap => write!(f, "trap"),
+            BlockOutcome::OutOfTicks => write!(f, "out_of_ticks"),
+        }
+    }
+}
+
+impl BlockOutcome {
+    /// Classifies the result of processing a block, passing on errors which are not caused by the
+    /// block.
+    fn of<T>(result: &Result<T, ScoutError>) -> Result<Self, &ScoutError> {
+        match result {
+            Ok(_) => Ok(BlockOutcome::Success),
+            Err(ScoutError::OutOfTicks) => Ok(BlockOutcome::OutOfTicks),
+            Err(err) if err.is_block_failure() => Ok(BlockOutcome::Trap),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// The expected outcome of processing a block. A block expected to fail does not abort the test;
+/// like with `tolerate_failures` the state of its execution environment is kept.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestBlockExpectation {
+    #[serde(default)]
+    pub result: BlockOutcome,
+    /// A substring of the error message of a failing block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The state root of the block's execution environment after processing it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_state: Option<String>,
+    /// The ticks used by a successful block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticks_used: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestShardState {
+    pub exec_env_states: Vec<String>,
+    /// The storage of each execution environment as a map of hex keys to hex values. It is only
+    /// compared in the post-state if given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec_env_storage: Option<Vec<BTreeMap<String, String>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestDeposit {
+    pub pubkey: String,
+    pub withdrawal_credentials: String,
+    pub amount: u64,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestFile {
+    /// The tick budget of each block, unless overridden by the environment or the block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticks: Option<u32>,
+    /// The tick budget of the blocks of each execution environment, keyed by its index, unless
+    /// overridden by the block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_ticks: Option<BTreeMap<u64, u32>>,
+    /// The costs charged by metering, overriding the runner defaults.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tick_costs: Option<TickCosts>,
+    /// Whether failing blocks are skipped instead of aborting the test, overriding the runner
+    /// option.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tolerate_failures: Option<bool>,
+    /// Whether deposit receipts may be produced in any order, overriding the runner option.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unordered_deposits: Option<bool>,
+    pub beacon_state: TestBeaconState,
+    pub shard_blocks: Vec<TestShardBlock>,
+    pub shard_pre_state: TestShardState,
+    pub shard_post_state: TestShardState,
+    pub deposit_receipts: Vec<TestDeposit>,
+}
+
+/// The formats test files can be written in, sharing the same schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestFormat {
+    Yaml,
+    Json,
+}
+
+impl TestFormat {
+    /// Detects the format of a test file from the extension of its path, or failing that from its
+    /// content: JSON test files are objects, so they start with `{`.
+    pub fn detect(path: &Path, content: &[u8]) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => TestFormat::Json,
+            Some("yaml") | Some("yml") => TestFormat::Yaml,
+            _ => match content.iter().find(|byte| !byte.is_ascii_whitespace()) {
+                Some(b'{') => TestFormat::Json,
+                _ => TestFormat::Yaml,
+            },
+        }
+    }
+
+    pub fn parse(self, content: &[u8]) -> Result<TestFile, ScoutError> {
+        Ok(match self {
+            TestFormat::Yaml => serde_yaml::from_slice(content)?,
+            TestFormat::Json => serde_json::from_slice(content)?,
+        })
+    }
+
+    pub fn serialize(self, test_file: &TestFile) -> Result<Vec<u8>, ScoutError> {
+        Ok(match self {
+            TestFormat::Yaml => serde_yaml::to_vec(test_file)?,
+            TestFormat::Json => {
+                let mut json = serde_json::to_vec_pretty(test_file)?;
+                json.push(b'\n');
+                json
+            }
+        })
+    }
+}
+
+/// Reads a test file in either format, returning it along with the format it was written in.
+pub fn load_test_file(filename: &str) -> Result<(TestFile, TestFormat), ScoutError> {
+    let content = std::fs::read(filename)?;
+    let format = TestFormat::detect(Path::new(filename), &content);
+    Ok((format.parse(&content)?, format))
+}
+
+/// Options for running test files which are not part of the test file itself.
+#[derive(Debug, Clone)]
+pub struct TestOptions {
+    /// The tick budget of each block if the test file does not specify one.
+    pub ticks: u32,
+    /// The costs charged by metering if the test file does not specify them. If metering is
+    /// disabled here it is disabled for every test file.
+    pub tick_costs: TickCosts,
+    /// Whether a block which traps, runs out of ticks or fails a host call is skipped, keeping the
+    /// pre-state of its execution environment and discarding its deposits, instead of aborting
+    /// the test.
+    pub tolerate_failures: bool,
+    /// Whether deposit receipts are compared regardless of their order. Their count must match
+    /// either way.
+    pub unordered_deposits: bool,
+    /// The directory relative paths in test files are resolved against, instead of the directory
+    /// of the test file.
+    pub base_dir: Option<PathBuf>,
+}
+
+impl Default for TestOptions {
+    fn default() -> Self {
+        TestOptions {
+            ticks: DEFAULT_TICKS,
+            tick_costs: TickCosts::default(),
+            tolerate_failures: false,
+            unordered_deposits: false,
+            base_dir: None,
+        }
+    }
+}
+
+impl TestOptions {
+    /// The directory relative paths in the test file `filename` are resolved against.
+    pub fn base_dir(&self, filename: &str) -> PathBuf {
+        match &self.base_dir {
+            Some(base_dir) => base_dir.clone(),
+            None => Path::new(filename)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The costs charged by metering: those of the test file, if it has any, or else those of the
+    /// options. Turning metering off in the options turns it off regardless of the test file.
+    pub fn tick_costs(&self, file_costs: Option<TickCosts>) -> TickCosts {
+        let mut costs = file_costs.unwrap_or_else(|| self.tick_costs.clone());
+        costs.enabled &= self.tick_costs.enabled;
+        costs
+    }
+}
+
+fn hex_to_slice(input: &str, output: &mut [u8]) -> Result<(), ScoutError> {
+    let tmp = input.from_hex()?;
+    if tmp.len() != output.len() {
+        return Err(ScoutError::InvalidInput(
+            "Length mismatch from hex input".to_string(),
+        ));
+    }
+    output.copy_from_slice(&tmp[..]);
+    Ok(())
+}
+
+impl TryFrom<&String> for Bytes32 {
+    type Error = ScoutError;
+    fn try_from(input: &String) -> Result<Self, Self::Error> {
+        let mut ret = Bytes32::default();
+        hex_to_slice(input, &mut ret.bytes)?;
+        Ok(ret)
+    }
+}
+
+impl TryFrom<String> for Hash {
+    type Error = ScoutError;
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let mut ret = Hash::default();
+        hex_to_slice(&input, &mut ret.0)?;
+        Ok(ret)
+    }
+}
+
+impl TryFrom<String> for BLSPubKey {
+    type Error = ScoutError;
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let mut ret = BLSPubKey::default();
+        hex_to_slice(&input, &mut ret.0)?;
+        Ok(ret)
+    }
+}
+
+impl TryFrom<String> for BLSSignature {
+    type Error = ScoutError;
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let mut ret = BLSSignature::default();
+        hex_to_slice(&input, &mut ret.0)?;
+        Ok(ret)
+    }
+}
+
+impl TestCode {
+    /// Loads or compiles the bytecode, resolving paths against `base_dir` and naming `what` the
+    /// code is for on failure.
+    pub fn load(&self, base_dir: &Path, what: &str) -> Result<Vec<u8>, ScoutError> {
+        let invalid = |err: &dyn fmt::Display| {
+            ScoutError::InvalidInput(format!("Invalid code of {}: {}", what, err))
+        };
+        match self {
+            TestCode::File(path) => {
+                let resolved = base_dir.join(path);
+                std::fs::read(&resolved).map_err(|err| {
+                    ScoutError::InvalidInput(format!(
+                        "Cannot read {} from {}: {}",
+                        what,
+                        resolved.display(),
+                        err
+                    ))
+                })
+            }
+            TestCode::Hex(hex) => hex.from_hex().map_err(|err| invalid(&err)),
+            TestCode::Base64(base64) => base64::decode(base64).map_err(|err| invalid(&err)),
+            TestCode::Wat(text) => wat::parse_str(text).map_err(|err| invalid(&err)),
+        }
+    }
+}
+
+impl TestScript {
+    pub fn load(&self, base_dir: &Path, what: &str) -> Result<Vec<u8>, ScoutError> {
+        match self {
+            TestScript::File(path) => TestCode::File(path.clone()).load(base_dir, what),
+            TestScript::Code(code) => code.load(base_dir, what),
+        }
+    }
+}
+
+impl TestBeaconState {
+    /// Loads the execution scripts and libraries, resolving relative paths against `base_dir`.
+    pub fn load(&self, base_dir: &Path) -> Result<BeaconState, ScoutError> {
+        let scripts: Result<Vec<ExecutionScript>, ScoutError> = self
+            .execution_scripts
+            .iter()
+            .enumerate()
+            .map(|(i, script)| {
+                Ok(ExecutionScript {
+                    code: script.load(base_dir, &format!("execution script {}", i))?,
+                })
+            })
+            .collect();
+        let libraries: Result<Vec<Library>, ScoutError> = if let Some(libraries) = &self.libraries {
+            libraries
+                .iter()
+                .enumerate()
+                .map(|(i, library)| {
+                    Ok(Library {
+                        name: library.name.to_string(),
+                        code: library
+                            .code
+                            .load(base_dir, &format!("library {} ({})", i, library.name))?,
+                    })
+                })
+                .collect()
+        } else {
+            Ok(Vec::new())
+        };
+        Ok(BeaconState {
+            execution_scripts: scripts?,
+            libraries: libraries?,
+        })
+    }
+}
+
+impl TryFrom<TestBeaconState> for BeaconState {
+    type Error = ScoutError;
+    /// Loads the execution scripts and libraries with paths relative to the working directory.
+    fn try_from(input: TestBeaconState) -> Result<Self, Self::Error> {
+        input.load(Path::new(""))
+    }
+}
+
+impl TestSszField {
+    /// The encoding of the field, which for lists is their variable-size part.
+    fn encode(&self) -> Result<Vec<u8>, ScoutError> {
+        Ok(match self {
+            TestSszField::Bool(value) => vec![*value as u8],
+            TestSszField::Uint8(value) => vec![*value],
+            TestSszField::Uint16(value) => value.to_le_bytes().to_vec(),
+            TestSszField::Uint32(value) => value.to_le_bytes().to_vec(),
+            TestSszField::Uint64(value) => value.to_le_bytes().to_vec(),
+            TestSszField::Uint256(value) => {
+                let number = if let Some(hex) = value.strip_prefix("0x") {
+                    // Parsing panics on more digits than fit.
+                    if hex.len() <= 64 {
+                        hex.parse::<U256>().ok()
+                    } else {
+                        None
+                    }
+                } else {
+                    U256::from_dec_str(value).ok()
+                };
+                let number = number.ok_or_else(|| {
+                    ScoutError::InvalidInput(format!("Invalid uint256 {}", value))
+                })?;
+                let mut bytes = [0u8; 32];
+                number.to_little_endian(&mut bytes);
+                bytes.to_vec()
+            }
+            TestSszField::Vector(hex) | TestSszField::List(hex) => hex.from_hex()?,
+        })
+    }
+}
+
+/// Encodes the fields as an SSZ container: the fixed-size fields and the offsets of the
+/// variable-size ones, followed by the variable-size fields.
+pub fn encode_ssz(fields: &[TestSszField]) -> Result<Vec<u8>, ScoutError> {
+    const OFFSET_SIZE: usize = 4;
+    let encoded = fields
+        .iter()
+        .map(|field| field.encode())
+        .collect::<Result<Vec<_>, _>>()?;
+    let is_variable = |field: &TestSszField| matches!(field, TestSszField::List(_));
+    let fixed_size: usize = fields
+        .iter()
+        .zip(&encoded)
+        .map(|(field, bytes)| {
+            if is_variable(field) {
+                OFFSET_SIZE
+            } else {
+                bytes.len()
+            }
+        })
+        .sum();
+
+    let mut fixed = Vec::with_capacity(fixed_size);
+    let mut variable = Vec::new();
+    for (field, bytes) in fields.iter().zip(encoded) {
+        if is_variable(field) {
+            let offset = (fixed_size + variable.len()) as u32;
+            fixed.extend_from_slice(&offset.to_le_bytes());
+            variable.extend(bytes);
+        } else {
+            fixed.extend(bytes);
+        }
+    }
+    fixed.extend(variable);
+    Ok(fixed)
+}
+
+impl TestBlockData {
+    /// Loads or encodes the data, resolving paths against `base_dir`.
+    pub fn load(&self, base_dir: &Path) -> Result<Vec<u8>, ScoutError> {
+        match self {
+            TestBlockData::Hex(hex) => Ok(hex.from_hex()?),
+            TestBlockData::File { file } => {
+                TestCode::File(file.clone()).load(base_dir, "block data")
+            }
+            TestBlockData::Ssz { ssz } => encode_ssz(ssz),
+        }
+    }
+}
+
+impl TestShardBlock {
+    /// Loads the block, resolving the path of its data against `base_dir`.
+    pub fn load(&self, base_dir: &Path) -> Result<ShardBlock, ScoutError> {
+        Ok(ShardBlock {
+            env: self.env,
+            data: ShardBlockBody {
+                data: self.data.load(base_dir)?,
+            },
+        })
+    }
+}
+
+impl TryFrom<TestShardBlock> for ShardBlock {
+    type Error = ScoutError;
+    /// Loads the block with the path of its data relative to the working directory.
+    fn try_from(input: TestShardBlock) -> Result<Self, Self::Error> {
+        input.load(Path::new(""))
+    }
+}
+
+impl TryFrom<TestShardState> for ShardState {
+    type Error = ScoutError;
+    fn try_from(input: TestShardState) -> Result<Self, Self::Error> {
+        let states: Result<Vec<Bytes32>, ScoutError> = input
+            .exec_env_states
+            .iter()
+            .map(|state| state.try_into())
+            .collect();
+
+        let storage: Result<Vec<Storage>, ScoutError> = input
+            .exec_env_storage
+            .unwrap_or_default()
+            .iter()
+            .map(|storage| {
+                let mut entries = Storage::new();
+                for (key, value) in storage {
+                    let value: Bytes32 = value.try_into()?;
+                    // Zero values are the same as absent keys.
+                    if value != Bytes32::default() {
+                        entries.insert(key.try_into()?, value);
+                    }
+                }
+                Ok(entries)
+            })
+            .collect();
+
+        Ok(ShardState {
+            exec_env_states: states?,
+            exec_env_storage: storage?,
+            slot: 0,
+            parent_block: ShardBlockHeader {},
+        })
+    }
+}
+
+impl TryFrom<TestDeposit> for Deposit {
+    type Error = ScoutError;
+    fn try_from(input: TestDeposit) -> Result<Self, Self::Error> {
+        Ok(Deposit {
+            pubkey: input.pubkey.try_into()?,
+            withdrawal_credentials: input.withdrawal_credentials.try_into()?,
+            amount: input.amount,
+            signature: input.signature.try_into()?,
+        })
+    }
+}
+
+impl From<&Deposit> for TestDeposit {
+    fn from(input: &Deposit) -> Self {
+        TestDeposit {
+            pubkey: input.pubkey.0.to_hex(),
+            withdrawal_credentials: input.withdrawal_credentials.0.to_hex(),
+            amount: input.amount,
+            signature: input.signature.0.to_hex(),
+        }
+    }
+}
+
+/// Describes the fields in which two deposits differ.
+fn deposit_diff(expected: &Deposit, actual: &Deposit) -> Vec<String> {
+    let mut diff = Vec::new();
+    if expected.pubkey != actual.pubkey {
+        diff.push(format!(
+            "pubkey expected {:?}, got {:?}",
+            expected.pubkey, actual.pubkey
+        ));
+    }
+    if expected.withdrawal_credentials != actual.withdrawal_credentials {
+        diff.push(format!(
+            "withdrawal_credentials expected {}, got {}",
+            expected.withdrawal_credentials.0.to_hex(),
+            actual.withdrawal_credentials.0.to_hex()
+        ));
+    }
+    if expected.amount != actual.amount {
+        diff.push(format!(
+            "amount expected {}, got {}",
+            expected.amount, actual.amount
+        ));
+    }
+    if expected.signature != actual.signature {
+        diff.push(format!(
+            "signature expected {:?}, got {:?}",
+            expected.signature, actual.signature
+        ));
+    }
+    diff
+}
+
+/// Compares the deposit receipts produced against the expected ones, returning a description of
+/// each mismatch. Unless `unordered` is set they must be produced in the same order.
+pub fn check_deposits(expected: &[Deposit], actual: &[Deposit], unordered: bool) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    if expected.len() != actual.len() {
+        mismatches.push(format!(
+            "expected {} deposits, got {}",
+            expected.len(),
+            actual.len()
+        ));
+    }
+    if unordered {
+        let mut matched = vec![false; actual.len()];
+        for deposit in expected {
+            match (0..actual.len()).find(|&i| !matched[i] && actual[i] == *deposit) {
+                Some(i) => matched[i] = true,
+                None => mismatches.push(format!("missing deposit {:?}", deposit)),
+            }
+        }
+        for (deposit, _) in actual.iter().zip(matched).filter(|(_, matched)| !matched) {
+            mismatches.push(format!("unexpected deposit {:?}", deposit));
+        }
+        return mismatches;
+    }
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(expected), Some(actual)) => {
+                for diff in deposit_diff(expected, actual) {
+                    mismatches.push(format!("deposit {}: {}", i, diff));
+                }
+            }
+            (Some(expected), None) => {
+                mismatches.push(format!("deposit {}: missing {:?}", i, expected))
+            }
+            (None, Some(actual)) => {
+                mismatches.push(format!("deposit {}: unexpected {:?}", i, actual))
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+    mismatches
+}
+
+/// The tick budget of a block: its own, or else that of its execution environment, or else the
+/// default of the test file or runner.
+fn block_ticks(block: &TestShardBlock, env_ticks: &BTreeMap<u64, u32>, default_ticks: u32) -> u32 {
+    block
+        .ticks
+        .or_else(|| env_ticks.get(&block.env).cloned())
+        .unwrap_or(default_ticks)
+}
+
+/// Compares the outcome of processing a block against the expectation, returning a description of
+/// each mismatch.
+fn check_block(
+    expect: &TestBlockExpectation,
+    outcome: BlockOutcome,
+    result: &Result<ShardBlockResult, ScoutError>,
+    shard_state: &ShardState,
+    env: usize,
+) -> Result<Vec<String>, ScoutError> {
+    let mut mismatches = Vec::new();
+    if outcome != expect.result {
+        mismatches.push(format!("expected {}, got {}", expect.result, outcome));
+    }
+    if let (Some(expected), Err(err)) = (&expect.error, result) {
+        if !err.to_string().contains(expected.as_str()) {
+            mismatches.push(format!(
+                "expected error containing \"{}\", got \"{}\"",
+                expected, err
+            ));
+        }
+    }
+    if let Some(expected) = &expect.post_state {
+        let expected: Bytes32 = expected.try_into()?;
+        let actual = shard_state.exec_env_states[env];
+        if expected != actual {
+            mismatches.push(format!(
+                "expected post state {}, got {}",
+                expected.bytes.to_hex(),
+                actual.bytes.to_hex()
+            ));
+        }
+    }
+    if let (Some(expected), Ok(result)) = (expect.ticks_used, result) {
+        if expected != result.ticks_used {
+            mismatches.push(format!(
+                "expected {} ticks used, got {}",
+                expected, result.ticks_used
+            ));
+        }
+    }
+    Ok(mismatches)
+}
+
+/// The storage of an execution environment as a map of hex keys to hex values.
+fn storage_of(state: &ShardState, env: usize) -> BTreeMap<String, String> {
+    state
+        .exec_env_storage
+        .get(env)
+        .map(|storage| {
+            storage
+                .iter()
+                .map(|(key, value)| (key.bytes.to_hex(), value.bytes.to_hex()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Loads a test file, in YAML or JSON, executes its shard blocks and compares the results against
+/// the expected post-state and deposit receipts.
+///
+/// Mismatches are recorded in the returned report. Errors are returned if the test file cannot be
+/// loaded. If processing a block fails in a way the test file does not expect, including when a
+/// block without an expected outcome fails and failures are not tolerated, the report of the blocks
+/// processed so far is returned with the error.
+pub fn process_yaml_test(filename: &str, options: &TestOptions) -> Result<TestReport, ScoutError> {
+    info!("Processing {}...", filename);
+    let (test_file, _) = load_test_file(filename)?;
+    debug!("{:#?}", test_file);
+
+    let base_dir = options.base_dir(filename);
+    let beacon_state = test_file.beacon_state.load(&base_dir)?;
+    let check_storage = test_file.shard_post_state.exec_env_storage.is_some();
+    let pre_state: ShardState = test_file.shard_pre_state.try_into()?;
+    let post_state: ShardState = test_file.shard_post_state.try_into()?;
+    let expected_deposit_receipts: Result<Vec<Deposit>, ScoutError> = test_file
+        .deposit_receipts
+        .into_iter()
+        .map(|deposit| deposit.try_into())
+        .collect();
+    let expected_deposit_receipts = expected_deposit_receipts?;
+
+    let mut report = TestReport::new(filename);
+    let mut shard_state = pre_state;
+    let mut deposit_receipts = Vec::new();
+    let default_ticks = test_file.ticks.unwrap_or(options.ticks);
+    let env_ticks = test_file.env_ticks.unwrap_or_default();
+    let tick_costs = options.tick_costs(test_file.tick_costs);
+    let tolerate_failures = test_file
+        .tolerate_failures
+        .unwrap_or(options.tolerate_failures);
+    let unordered_deposits = test_file
+        .unordered_deposits
+        .unwrap_or(options.unordered_deposits);
+    for (i, mut block) in test_file.shard_blocks.into_iter().enumerate() {
+        let ticks = block_ticks(&block, &env_ticks, default_ticks);
+        let env = block.env as usize;
+        let expect = block.expect.take();
+        let result = block.load(&base_dir).and_then(|block| {
+            process_shard_block(
+                &mut shard_state,
+                &beacon_state,
+                Some(block),
+                ticks,
+                &tick_costs,
+            )
+        });
+        let outcome = BlockOutcome::of(&result).ok();
+        report
+            .blocks
+            .push(BlockReport::new(i, env, outcome, &result, &shard_state));
+        let outcome = match outcome {
+            Some(outcome) => outcome,
+            None => return Ok(report.abort(&result.unwrap_err())),
+        };
+
+        if let Some(expect) = expect {
+            match check_block(&expect, outcome, &result, &shard_state, env) {
+                Ok(mismatches) => report
+                    .checks
+                    .push(CheckResult::new(format!("block {}", i), mismatches)),
+                Err(err) => return Ok(report.abort(&err)),
+            }
+        } else if outcome != BlockOutcome::Success && !tolerate_failures {
+            return Ok(report.abort(&result.unwrap_err()));
+        }
+        if let Ok(mut result) = result {
+            deposit_receipts.append(&mut result.deposit_receipts);
+        }
+    }
+
+    let mismatches = check_deposits(
+        &expected_deposit_receipts,
+        &deposit_receipts,
+        unordered_deposits,
+    );
+    report
+        .checks
+        .push(CheckResult::new("deposit receipts", mismatches));
+
+    debug!("{}", shard_state);
+    let mut mismatches = Vec::new();
+    if shard_state.exec_env_states != post_state.exec_env_states {
+        mismatches.push(format!("expected {}, got {}", post_state, shard_state));
+    }
+    report.checks.push(CheckResult::new("state", mismatches));
+
+    if check_storage {
+        let count = shard_state
+            .exec_env_storage
+            .len()
+            .max(post_state.exec_env_storage.len());
+        let mut mismatches = Vec::new();
+        for env in 0..count {
+            let expected = storage_of(&post_state, env);
+            let actual = storage_of(&shard_state, env);
+            if expected != actual {
+                mismatches.push(format!(
+                    "expected {:?} for environment {}, got {:?}",
+                    expected, env, actual
+                ));
+            }
+        }
+        report.checks.push(CheckResult::new("storage", mismatches));
+    }
+
+    Ok(report)
+}
+
+/// Loads a test file and executes its shard blocks, returning the test file with
+/// `shard_post_state`, `deposit_receipts` and the expected outcome of each block filled in from
+/// the results, along with the format it was written in.
+///
+/// As when running the test, a failing block aborts filling unless failures are tolerated or the
+/// block is expected to fail; its outcome is then recorded as the expectation.
+pub fn fill_test(
+    filename: &str,
+    options: &TestOptions,
+) -> Result<(TestFile, TestFormat), ScoutError> {
+    info!("Filling {}...", filename);
+    let (mut test_file, format) = load_test_file(filename)?;
+
+    let base_dir = options.base_dir(filename);
+    let beacon_state = test_file.beacon_state.load(&base_dir)?;
+    let mut shard_state: ShardState = test_file.shard_pre_state.clone().try_into()?;
+    let mut deposit_receipts = Vec::new();
+    let default_ticks = test_file.ticks.unwrap_or(options.ticks);
+    let env_ticks = test_file.env_ticks.clone().unwrap_or_default();
+    let tick_costs = options.tick_costs(test_file.tick_costs.clone());
+    let tolerate_failures = test_file
+        .tolerate_failures
+        .unwrap_or(options.tolerate_failures);
+    for block in test_file.shard_blocks.iter_mut() {
+        let ticks = block_ticks(block, &env_ticks, default_ticks);
+        let env = block.env as usize;
+        let result = process_shard_block(
+            &mut shard_state,
+            &beacon_state,
+            Some(block.load(&base_dir)?),
+            ticks,
+            &tick_costs,
+        );
+        let outcome = match BlockOutcome::of(&result) {
+            Ok(outcome) => outcome,
+            Err(_) => return Err(result.unwrap_err()),
+        };
+        let expects_failure = block
+            .expect
+            .as_ref()
+            .is_some_and(|expect| expect.result != BlockOutcome::Success);
+        if outcome != BlockOutcome::Success && !expects_failure && !tolerate_failures {
+            return Err(result.unwrap_err());
+        }
+        let post_state = Some(shard_state.exec_env_states[env].bytes.to_hex());
+        block.expect = Some(match result {
+            Ok(mut result) => {
+                deposit_receipts.append(&mut result.deposit_receipts);
+                TestBlockExpectation {
+                    result: outcome,
+                    error: None,
+                    post_state,
+                    ticks_used: Some(result.ticks_used),
+                }
+            }
+            Err(err) => TestBlockExpectation {
+                result: outcome,
+                error: Some(err.to_string()),
+                post_state,
+                ticks_used: None,
+            },
+        });
+    }
+
+    // Storage is only filled in if it is used, as otherwise it is not compared.
+    let exec_env_storage = if test_file.shard_pre_state.exec_env_storage.is_some()
+        || test_file.shard_post_state.exec_env_storage.is_some()
+        || shard_state
+            .exec_env_storage
+            .iter()
+            .any(|storage| !storage.is_empty())
+    {
+        Some(
+            (0..shard_state.exec_env_states.len())
+                .map(|env| storage_of(&shard_state, env))
+                .collect(),
+        )
+    } else {
+        None
+    };
+    test_file.shard_post_state = TestShardState {
+        exec_env_states: shard_state
+            .exec_env_states
+            .iter()
+            .map(|state| state.bytes.to_hex())
+            .collect(),
+        exec_env_storage,
+    };
+    test_file.deposit_receipts = deposit_receipts.iter().map(TestDeposit::from).collect();
+    Ok((test_file, format))
+}
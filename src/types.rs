@@ -1,5 +1,5 @@
 /// An array of 256 bits.
-#[derive(Default, PartialEq, Copy, Clone, Debug)]
+#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Debug)]
 pub struct Bytes32 {
     pub bytes: [u8; 32],
 }
@@ -0,0 +1,394 @@
+//! Tests of the 256-bit `bignum` host functions.
+
+mod common;
+
+use common::{assert_charges, call_host, from_hex, run_wat, to_hex, wat_hex, INPUT};
+use phase2_scout::{FieldCost, ScoutError, TickCosts, DEFAULT_TICKS};
+use primitive_types::U256;
+
+/// Where the operands a and b and the modulus m are placed.
+const A: u32 = INPUT;
+const B: u32 = INPUT + 64;
+const M: u32 = INPUT + 128;
+
+fn big_endian(value: U256) -> [u8; 32] {
+    let mut raw = [0u8; 32];
+    value.to_big_endian(&mut raw);
+    raw
+}
+
+/// Calls `bignum.<function>` with the given signature and arguments after placing the operands,
+/// and returns the 256-bit result written to 0 and the returned flag.
+fn call(
+    function: &str,
+    signature: &str,
+    args: &str,
+    a: U256,
+    b: U256,
+    m: U256,
+) -> Result<(U256, i32), ScoutError> {
+    let call = call_host(
+        "bignum",
+        function,
+        signature,
+        args,
+        &[
+            (A, &big_endian(a)),
+            (B, &big_endian(b)),
+            (M, &big_endian(m)),
+        ],
+        DEFAULT_TICKS,
+        &TickCosts::default(),
+    )?;
+    Ok((U256::from_big_endian(&call.output[..32]), call.result))
+}
+
+fn binary(function: &str, a: U256, b: U256) -> Result<U256, ScoutError> {
+    call(
+        function,
+        "(param i32 i32 i32)",
+        &format!("(i32.const {}) (i32.const {}) (i32.const 0)", A, B),
+        a,
+        b,
+        U256::zero(),
+    )
+    .map(|(c, _)| c)
+}
+
+fn modular(function: &str, a: U256, b: U256, m: U256) -> Result<U256, ScoutError> {
+    call(
+        function,
+        "(param i32 i32 i32 i32)",
+        &format!(
+            "(i32.const {}) (i32.const {}) (i32.const {}) (i32.const 0)",
+            A, B, M
+        ),
+        a,
+        b,
+        m,
+    )
+    .map(|(c, _)| c)
+}
+
+fn compare(function: &str, a: U256, b: U256) -> i32 {
+    call(
+        function,
+        "(param i32 i32) (result i32)",
+        &format!("(i32.const {}) (i32.const {})", A, B),
+        a,
+        b,
+        U256::zero(),
+    )
+    .expect("successful execution")
+    .1
+}
+
+fn wrapping(function: &str, a: U256, b: U256) -> (U256, i32) {
+    call(
+        function,
+        "(param i32 i32 i32) (result i32)",
+        &format!("(i32.const {}) (i32.const {}) (i32.const 0)", A, B),
+        a,
+        b,
+        U256::zero(),
+    )
+    .expect("successful execution")
+}
+
+fn is_host_failure<T>(ret: Result<T, ScoutError>) -> bool {
+    matches!(ret, Err(ScoutError::HostCall { .. }))
+}
+
+#[test]
+fn add_and_sub() {
+    assert_eq!(binary("add256", 2.into(), 3.into()).unwrap(), 5.into());
+    assert_eq!(binary("sub256", 5.into(), 3.into()).unwrap(), 2.into());
+    assert!(is_host_failure(binary("add256", U256::MAX, 1.into())));
+    assert!(is_host_failure(binary("sub256", 0.into(), 1.into())));
+}
+
+#[test]
+fn mul() {
+    let a = U256::from(u128::MAX);
+    assert_eq!(
+        binary("mul256", a, a).unwrap(),
+        U256::MAX - (a << 1) // (2^128 - 1)^2 = 2^256 - 2^129 + 1
+    );
+    assert!(is_host_failure(binary("mul256", U256::MAX, 2.into())));
+}
+
+#[test]
+fn div_and_mod() {
+    assert_eq!(binary("div256", 17.into(), 5.into()).unwrap(), 3.into());
+    assert_eq!(binary("mod256", 17.into(), 5.into()).unwrap(), 2.into());
+    assert!(is_host_failure(binary("div256", 1.into(), 0.into())));
+    assert!(is_host_failure(binary("mod256", 1.into(), 0.into())));
+}
+
+#[test]
+fn addmod() {
+    assert_eq!(
+        modular("addmod256", 7.into(), 5.into(), 10.into()).unwrap(),
+        2.into()
+    );
+    // The intermediate sum does not fit into 256 bits.
+    assert_eq!(
+        modular("addmod256", U256::MAX, U256::MAX, U256::MAX).unwrap(),
+        0.into()
+    );
+    assert_eq!(
+        modular("addmod256", U256::MAX, 2.into(), U256::MAX - 1).unwrap(),
+        3.into()
+    );
+    assert!(is_host_failure(modular(
+        "addmod256",
+        1.into(),
+        1.into(),
+        0.into()
+    )));
+}
+
+#[test]
+fn mulmod() {
+    assert_eq!(
+        modular("mulmod256", 7.into(), 5.into(), 10.into()).unwrap(),
+        5.into()
+    );
+    // (2^256 - 1)^2 = 2^512 - 2^257 + 1
+    assert_eq!(
+        modular("mulmod256", U256::MAX, U256::MAX, U256::one() << 128).unwrap(),
+        1.into()
+    );
+    assert_eq!(
+        modular("mulmod256", U256::MAX, U256::MAX, U256::MAX).unwrap(),
+        0.into()
+    );
+    assert!(is_host_failure(modular(
+        "mulmod256",
+        1.into(),
+        1.into(),
+        0.into()
+    )));
+}
+
+#[test]
+fn comparisons() {
+    assert_eq!(compare("lt256", 1.into(), 2.into()), 1);
+    assert_eq!(compare("lt256", 2.into(), 2.into()), 0);
+    assert_eq!(compare("gt256", U256::MAX, 2.into()), 1);
+    assert_eq!(compare("gt256", 2.into(), 2.into()), 0);
+    assert_eq!(compare("eq256", U256::MAX, U256::MAX), 1);
+    assert_eq!(compare("eq256", U256::MAX, 0.into()), 0);
+}
+
+#[test]
+fn wrapping_arithmetic() {
+    assert_eq!(
+        wrapping("wrappingAdd256", 2.into(), 3.into()),
+        (5.into(), 0)
+    );
+    assert_eq!(
+        wrapping("wrappingAdd256", U256::MAX, 2.into()),
+        (1.into(), 1)
+    );
+    assert_eq!(
+        wrapping("wrappingSub256", 5.into(), 3.into()),
+        (2.into(), 0)
+    );
+    assert_eq!(
+        wrapping("wrappingSub256", 0.into(), 1.into()),
+        (U256::MAX, 1)
+    );
+    assert_eq!(
+        wrapping("wrappingMul256", 2.into(), 3.into()),
+        (6.into(), 0)
+    );
+    assert_eq!(
+        wrapping("wrappingMul256", U256::one() << 255, 2.into()),
+        (0.into(), 1)
+    );
+}
+
+const BN128_FQ: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47";
+const BLS12_381_FQ: &str = "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab";
+
+fn left_pad(value: u64, size: usize) -> String {
+    format!("{:0width$x}", value, width = size * 2)
+}
+
+/// Runs `body` with the given hex strings at offsets 0, 64, 128 and 192, and returns the `size`
+/// bytes at 256 as hex.
+fn run_field(body: &str, segments: &[&str], size: usize) -> Result<String, ScoutError> {
+    let data: String = segments
+        .iter()
+        .enumerate()
+        .map(|(i, hex)| format!("(data (i32.const {}) \"{}\")", i * 64, wat_hex(hex)))
+        .collect();
+    let source = format!(
+        r#"(module
+            (import "bignum" "mulModMont256" (func $mul256 (param i32 i32 i32 i32)))
+            (import "bignum" "addModMont256" (func $add256 (param i32 i32 i32 i32)))
+            (import "bignum" "mulModMont384" (func $mul384 (param i32 i32 i32 i32)))
+            (import "bignum" "subModMont384" (func $sub384 (param i32 i32 i32 i32)))
+            (import "bignum" "fieldMul" (func $fieldMul (param i32 i32 i32 i32)))
+            (import "bignum" "fieldAdd" (func $fieldAdd (param i32 i32 i32 i32)))
+            (import "bignum" "fieldToMont" (func $toMont (param i32 i32 i32)))
+            (import "bignum" "fieldFromMont" (func $fromMont (param i32 i32 i32)))
+            (import "eth2" "pushNewDeposit" (func $push (param i32 i32)))
+            (memory (export "memory") 1)
+            {}
+            (func (export "main") {} (call $push (i32.const 256) (i32.const {}))))"#,
+        data, body, size
+    );
+    let result = run_wat(&source, DEFAULT_TICKS, &TickCosts::default())?;
+    Ok(to_hex(&result.deposits[0]))
+}
+
+#[test]
+fn field_multiplication_in_montgomery_form() {
+    // Convert 6 and 7 into Montgomery form, multiply and convert back.
+    let ret = run_field(
+        "(call $toMont (i32.const 0) (i32.const 0) (i32.const 0))
+         (call $toMont (i32.const 0) (i32.const 64) (i32.const 64))
+         (call $fieldMul (i32.const 0) (i32.const 0) (i32.const 64) (i32.const 128))
+         (call $fromMont (i32.const 0) (i32.const 128) (i32.const 256))",
+        &[&left_pad(6, 32), &left_pad(7, 32)],
+        32,
+    );
+    assert_eq!(ret.unwrap(), left_pad(42, 32));
+}
+
+#[test]
+fn field_presets_match_explicit_modulus() {
+    let a = "0000000000000000000000000000000000000000000000000000000000abcdef";
+    let b = "1234567890000000000000000000000000000000000000000000000000000001";
+    let preset = run_field(
+        "(call $fieldMul (i32.const 0) (i32.const 0) (i32.const 64) (i32.const 256))",
+        &[a, b],
+        32,
+    );
+    let explicit = run_field(
+        "(call $mul256 (i32.const 0) (i32.const 64) (i32.const 128) (i32.const 256))",
+        &[a, b, BN128_FQ],
+        32,
+    );
+    assert_eq!(preset.unwrap(), explicit.unwrap());
+
+    let preset = run_field(
+        "(call $fieldAdd (i32.const 0) (i32.const 0) (i32.const 64) (i32.const 256))",
+        &[a, b],
+        32,
+    );
+    let explicit = run_field(
+        "(call $add256 (i32.const 0) (i32.const 64) (i32.const 128) (i32.const 256))",
+        &[a, b, BN128_FQ],
+        32,
+    );
+    assert_eq!(preset.unwrap(), explicit.unwrap());
+}
+
+#[test]
+fn bls12_381_subtraction_wraps_around() {
+    // 0 - 1 = modulus - 1
+    let ret = run_field(
+        "(call $sub384 (i32.const 0) (i32.const 64) (i32.const 128) (i32.const 256))",
+        &[&left_pad(0, 48), &left_pad(1, 48), BLS12_381_FQ],
+        48,
+    );
+    assert_eq!(
+        ret.unwrap(),
+        "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaaa"
+    );
+}
+
+#[test]
+fn bls12_381_montgomery_one() {
+    // R mod p is the Montgomery form of 1, so multiplying it by itself yields itself.
+    let r = "15f65ec3fa80e4935c071a97a256ec6d77ce5853705257455f48985753c758baebf4000bc40c0002760900000002fffd";
+    let ret = run_field(
+        "(call $mul384 (i32.const 0) (i32.const 0) (i32.const 128) (i32.const 256))",
+        &[r, "", BLS12_381_FQ],
+        48,
+    );
+    assert_eq!(ret.unwrap(), r);
+}
+
+#[test]
+fn field_arithmetic_failures() {
+    // Unknown field
+    assert!(is_host_failure(run_field(
+        "(call $fieldMul (i32.const 9) (i32.const 0) (i32.const 64) (i32.const 256))",
+        &[],
+        32,
+    )));
+    // Operand not reduced
+    assert!(is_host_failure(run_field(
+        "(call $fieldMul (i32.const 0) (i32.const 0) (i32.const 64) (i32.const 256))",
+        &[BN128_FQ, &left_pad(1, 32)],
+        32,
+    )));
+    // Even modulus
+    assert!(is_host_failure(run_field(
+        "(call $mul256 (i32.const 0) (i32.const 64) (i32.const 128) (i32.const 256))",
+        &[&left_pad(1, 32), &left_pad(1, 32), &left_pad(10, 32)],
+        32,
+    )));
+    // Modulus out of bounds
+    assert!(is_host_failure(run_field(
+        "(call $mul384 (i32.const 0) (i32.const 64) (i32.const 65500) (i32.const 256))",
+        &[],
+        48,
+    )));
+}
+
+#[test]
+fn bignum_functions_are_charged() {
+    let costs = TickCosts {
+        bignum: 7,
+        montgomery_add: FieldCost {
+            base: 2,
+            per_limb: 1,
+        },
+        montgomery_mul: FieldCost {
+            base: 10,
+            per_limb: 4,
+        },
+        ..TickCosts::disabled()
+    };
+    let modulus = from_hex(BLS12_381_FQ);
+    let charged = |function: &str, signature: &str, args: String, ticks| {
+        call_host(
+            "bignum",
+            function,
+            signature,
+            &args,
+            &[(M, &modulus)],
+            ticks,
+            &costs,
+        )
+    };
+    assert_charges(7, |ticks| {
+        let args = format!("(i32.const {}) (i32.const {}) (i32.const 0)", A, B);
+        charged("add256", "(param i32 i32 i32)", args, ticks)
+    });
+    // The BLS12-381 modulus has six limbs.
+    assert_charges(34, |ticks| {
+        let args = format!(
+            "(i32.const {}) (i32.const {}) (i32.const {}) (i32.const 0)",
+            A, B, M
+        );
+        charged("mulModMont384", "(param i32 i32 i32 i32)", args, ticks)
+    });
+    // The BN128 Fq modulus has four limbs.
+    assert_charges(6, |ticks| {
+        let args = format!(
+            "(i32.const 0) (i32.const {}) (i32.const {}) (i32.const 0)",
+            A, B
+        );
+        charged("fieldAdd", "(param i32 i32 i32 i32)", args, ticks)
+    });
+    assert_charges(26, |ticks| {
+        let args = format!("(i32.const 0) (i32.const {}) (i32.const 0)", A);
+        charged("fieldToMont", "(param i32 i32 i32)", args, ticks)
+    });
+}
@@ -0,0 +1,104 @@
+//! Conformance tests for `eth2_blockDataCopy`, which copies `length` bytes of the block data
+//! starting at `offset` into memory at `ptr`.
+
+mod common;
+
+use common::run_with_data as run;
+use phase2_scout::ScoutError;
+
+/// Copies the block data into memory in chunks of `chunk` bytes (the last one being shorter) and
+/// pushes the reassembled data back as a deposit.
+fn copy_in_chunks(data: &[u8], chunk: u32) -> Vec<u8> {
+    let source = format!(
+        r#"(module
+            (import "eth2" "blockDataSize" (func $size (result i32)))
+            (import "eth2" "blockDataCopy" (func $copy (param i32 i32 i32)))
+            (import "eth2" "pushNewDeposit" (func $push (param i32 i32)))
+            (memory (export "memory") 1)
+            (func (export "main")
+                (local $offset i32)
+                (local $length i32)
+                (block $done
+                    (loop $next
+                        (br_if $done (i32.ge_u (local.get $offset) (call $size)))
+                        (local.set $length (i32.sub (call $size) (local.get $offset)))
+                        (if (i32.gt_u (local.get $length) (i32.const {chunk}))
+                            (then (local.set $length (i32.const {chunk}))))
+                        (call $copy (local.get $offset) (local.get $offset) (local.get $length))
+                        (local.set $offset (i32.add (local.get $offset) (local.get $length)))
+                        (br $next)))
+                (call $push (i32.const 0) (call $size))))"#,
+        chunk = chunk
+    );
+    let mut result = run(&source, data).expect("successful execution");
+    assert_eq!(result.deposits.len(), 1);
+    result.deposits.remove(0)
+}
+
+fn copy(offset: u32, length: u32, data: &[u8]) -> Result<Vec<u8>, ScoutError> {
+    let source = format!(
+        r#"(module
+            (import "eth2" "blockDataCopy" (func $copy (param i32 i32 i32)))
+            (import "eth2" "pushNewDeposit" (func $push (param i32 i32)))
+            (memory (export "memory") 1)
+            (func (export "main")
+                (call $copy (i32.const 0) (i32.const {offset}) (i32.const {length}))
+                (call $push (i32.const 0) (i32.const {length}))))"#,
+        offset = offset as i32,
+        length = length as i32
+    );
+    let mut result = run(&source, data)?;
+    Ok(result.deposits.remove(0))
+}
+
+fn block_data() -> Vec<u8> {
+    (0..1000u32).map(|i| (i * 7 + 3) as u8).collect()
+}
+
+#[test]
+fn chunked_copies_reconstruct_block_data() {
+    let data = block_data();
+    for chunk in &[1, 3, 7, 32, 333, 999, 1000, 4096] {
+        assert_eq!(copy_in_chunks(&data, *chunk), data, "chunk size {}", chunk);
+    }
+}
+
+#[test]
+fn chunked_copies_of_empty_block_data() {
+    assert_eq!(copy_in_chunks(&[], 8), Vec::<u8>::new());
+}
+
+#[test]
+fn copy_from_offset() {
+    let data = block_data();
+    assert_eq!(copy(10, 20, &data).unwrap(), data[10..30].to_vec());
+    assert_eq!(copy(980, 20, &data).unwrap(), data[980..].to_vec());
+}
+
+#[test]
+fn copy_nothing_at_end() {
+    let data = block_data();
+    assert_eq!(copy(1000, 0, &data).unwrap(), Vec::<u8>::new());
+}
+
+#[test]
+fn copy_past_end() {
+    let data = block_data();
+    assert!(matches!(
+        copy(990, 11, &data),
+        Err(ScoutError::HostCall { .. })
+    ));
+    assert!(matches!(
+        copy(1001, 0, &data),
+        Err(ScoutError::HostCall { .. })
+    ));
+}
+
+#[test]
+fn copy_offset_overflow() {
+    let data = block_data();
+    assert!(matches!(
+        copy(u32::MAX, 2, &data),
+        Err(ScoutError::HostCall { .. })
+    ));
+}
@@ -0,0 +1,254 @@
+mod common;
+
+use blst::min_pk::{AggregateSignature, SecretKey, Signature};
+use common::{assert_charges, call_host, HostCall, INPUT};
+use phase2_scout::{AggregateCost, ScoutError, TickCosts, DEFAULT_TICKS};
+
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+fn secret_key(seed: u8) -> SecretKey {
+    SecretKey::key_gen(&[seed; 32], &[]).unwrap()
+}
+
+fn sign(seed: u8, message: &[u8]) -> Signature {
+    secret_key(seed).sign(message, DST, &[])
+}
+
+fn pubkeys(seeds: &[u8]) -> Vec<u8> {
+    seeds
+        .iter()
+        .flat_map(|seed| secret_key(*seed).sk_to_pk().compress().to_vec())
+        .collect()
+}
+
+fn aggregate(signatures: &[Signature]) -> Signature {
+    let signatures: Vec<&Signature> = signatures.iter().collect();
+    AggregateSignature::aggregate(&signatures, true)
+        .unwrap()
+        .to_signature()
+}
+
+/// The inputs placed into memory before calling a host function.
+struct Inputs<'a> {
+    pubkeys: &'a [u8],
+    messages: &'a [u8],
+    signature: &'a [u8],
+}
+
+/// Where the signature, the public keys and the messages are placed.
+const SIGNATURE: u32 = INPUT;
+const PUBKEYS: u32 = INPUT + 1024;
+const MESSAGES: u32 = INPUT + 2048;
+
+/// Calls `crypto.<function>` with the given parameter types and arguments after placing the
+/// inputs at `SIGNATURE`, `PUBKEYS` and `MESSAGES`.
+fn call(
+    function: &str,
+    params: &str,
+    args: &str,
+    inputs: &Inputs,
+    ticks: u32,
+    costs: &TickCosts,
+) -> Result<HostCall, ScoutError> {
+    call_host(
+        "crypto",
+        function,
+        &format!("(param {}) (result i32)", params),
+        args,
+        &[
+            (SIGNATURE, inputs.signature),
+            (PUBKEYS, inputs.pubkeys),
+            (MESSAGES, inputs.messages),
+        ],
+        ticks,
+        costs,
+    )
+}
+
+fn verify(pubkey: &[u8], message: &[u8], signature: &[u8]) -> Result<i32, ScoutError> {
+    let args = format!(
+        "(i32.const {}) (i32.const {}) (i32.const {}) (i32.const {})",
+        PUBKEYS,
+        MESSAGES,
+        message.len(),
+        SIGNATURE
+    );
+    let inputs = Inputs {
+        pubkeys: pubkey,
+        messages: message,
+        signature,
+    };
+    let call = call(
+        "blsVerify",
+        "i32 i32 i32 i32",
+        &args,
+        &inputs,
+        DEFAULT_TICKS,
+        &TickCosts::default(),
+    )?;
+    Ok(call.result)
+}
+
+fn aggregate_verify(
+    function: &str,
+    count: u32,
+    message_length: usize,
+    inputs: &Inputs,
+    ticks: u32,
+    costs: &TickCosts,
+) -> Result<HostCall, ScoutError> {
+    let args = format!(
+        "(i32.const {}) (i32.const {}) (i32.const {}) (i32.const {}) (i32.const {})",
+        PUBKEYS, count, MESSAGES, message_length, SIGNATURE
+    );
+    call(function, "i32 i32 i32 i32 i32", &args, inputs, ticks, costs)
+}
+
+#[test]
+fn verify_single_signature() {
+    let signature = sign(1, b"hello").compress();
+    assert_eq!(verify(&pubkeys(&[1]), b"hello", &signature).unwrap(), 1);
+    assert_eq!(verify(&pubkeys(&[1]), b"hellp", &signature).unwrap(), 0);
+    assert_eq!(verify(&pubkeys(&[2]), b"hello", &signature).unwrap(), 0);
+}
+
+#[test]
+fn invalid_encodings_fail_verification() {
+    let signature = sign(1, b"hello").compress();
+    assert_eq!(verify(&[0xff; 48], b"hello", &signature).unwrap(), 0);
+    assert_eq!(verify(&pubkeys(&[1]), b"hello", &[0xff; 96]).unwrap(), 0);
+    // The point at infinity is not a valid public key.
+    let mut infinity = [0u8; 48];
+    infinity[0] = 0xc0;
+    assert_eq!(verify(&infinity, b"hello", &signature).unwrap(), 0);
+}
+
+#[test]
+fn aggregate_verify_distinct_messages() {
+    let messages: Vec<u8> = (0..3u8).flat_map(|i| vec![i; 32]).collect();
+    let signature = aggregate(&[
+        sign(1, &messages[0..32]),
+        sign(2, &messages[32..64]),
+        sign(3, &messages[64..96]),
+    ])
+    .compress();
+    let check = |pubkeys: &[u8], count| {
+        let inputs = Inputs {
+            pubkeys,
+            messages: &messages,
+            signature: &signature,
+        };
+        aggregate_verify(
+            "blsAggregateVerify",
+            count,
+            32,
+            &inputs,
+            DEFAULT_TICKS,
+            &TickCosts::default(),
+        )
+        .unwrap()
+        .result
+    };
+    assert_eq!(check(&pubkeys(&[1, 2, 3]), 3), 1);
+    assert_eq!(check(&pubkeys(&[1, 3, 2]), 3), 0);
+    assert_eq!(check(&pubkeys(&[1, 2]), 2), 0);
+    assert_eq!(check(&[], 0), 0);
+}
+
+#[test]
+fn fast_aggregate_verify_common_message() {
+    let message = b"attestation";
+    let signature = aggregate(&[sign(1, message), sign(2, message), sign(3, message)]).compress();
+    let check = |pubkeys: &[u8], count| {
+        let inputs = Inputs {
+            pubkeys,
+            messages: message,
+            signature: &signature,
+        };
+        aggregate_verify(
+            "blsFastAggregateVerify",
+            count,
+            message.len(),
+            &inputs,
+            DEFAULT_TICKS,
+            &TickCosts::default(),
+        )
+        .unwrap()
+        .result
+    };
+    assert_eq!(check(&pubkeys(&[3, 1, 2]), 3), 1);
+    assert_eq!(check(&pubkeys(&[1, 2, 4]), 3), 0);
+    assert_eq!(check(&[], 0), 0);
+}
+
+#[test]
+fn verification_is_charged_per_pubkey() {
+    let message = b"attestation";
+    let pubkeys = pubkeys(&[1, 2]);
+    let signature = aggregate(&[sign(1, message), sign(2, message)]).compress();
+    let inputs = Inputs {
+        pubkeys: &pubkeys,
+        messages: message,
+        signature: &signature,
+    };
+    let costs = TickCosts {
+        bls_fast_aggregate_verify: AggregateCost {
+            base: 1000,
+            per_pubkey: 100,
+        },
+        ..TickCosts::disabled()
+    };
+    assert_charges(1200, |ticks| {
+        aggregate_verify(
+            "blsFastAggregateVerify",
+            2,
+            message.len(),
+            &inputs,
+            ticks,
+            &costs,
+        )
+    });
+}
+
+const NO_INPUTS: Inputs = Inputs {
+    pubkeys: &[],
+    messages: &[],
+    signature: &[],
+};
+
+#[test]
+fn huge_pubkey_count_runs_out_of_ticks() {
+    assert!(matches!(
+        aggregate_verify(
+            "blsAggregateVerify",
+            u32::MAX,
+            32,
+            &NO_INPUTS,
+            DEFAULT_TICKS,
+            &TickCosts::default()
+        ),
+        Err(ScoutError::OutOfTicks)
+    ));
+}
+
+#[test]
+fn pubkeys_out_of_bounds() {
+    let costs = TickCosts {
+        bls_aggregate_verify: AggregateCost {
+            base: 0,
+            per_pubkey: 0,
+        },
+        ..TickCosts::default()
+    };
+    assert!(matches!(
+        aggregate_verify(
+            "blsAggregateVerify",
+            2000,
+            32,
+            &NO_INPUTS,
+            DEFAULT_TICKS,
+            &costs
+        ),
+        Err(ScoutError::HostCall { .. })
+    ));
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::{assert_charges, call_host, from_hex, to_hex, HostCall, INPUT};
+use phase2_scout::{PairingCost, ScoutError, TickCosts, DEFAULT_TICKS};
+
+const G1: &str = "00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000002";
+const MINUS_G1: &str = "000000000000000000000000000000000000000000000000000000000000000130644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45";
+const TWO_G1: &str = "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd315ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4";
+const G2: &str = "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c21800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+/// The order of the BN128 groups.
+const ORDER: &str = "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001";
+
+/// Calls `crypto.<function>` with the given parameter types and arguments after placing the
+/// hex encoded `input` at `INPUT`.
+fn call(
+    function: &str,
+    params: &str,
+    args: &str,
+    input: &str,
+    ticks: u32,
+    costs: &TickCosts,
+) -> Result<HostCall, ScoutError> {
+    call_host(
+        "crypto",
+        function,
+        &format!("(param {}) (result i32)", params),
+        args,
+        &[(INPUT, &from_hex(input))],
+        ticks,
+        costs,
+    )
+}
+
+/// Returns the result code and the point written by `bn128Add` or `bn128Mul`.
+fn point_op(function: &str, a: &str, b: &str) -> (i32, String) {
+    let call = call(
+        function,
+        "i32 i32 i32",
+        &format!(
+            "(i32.const {}) (i32.const {}) (i32.const 0)",
+            INPUT,
+            INPUT + 64
+        ),
+        &format!("{}{}", a, b),
+        DEFAULT_TICKS,
+        &TickCosts::default(),
+    )
+    .unwrap();
+    (call.result, to_hex(&call.output[..64]))
+}
+
+/// Runs `bn128Pairing` over the G1 and G2 points in `points`, which alternate.
+fn pairing(points: &[&str], ticks: u32, costs: &TickCosts) -> Result<HostCall, ScoutError> {
+    let args = format!("(i32.const {}) (i32.const {})", INPUT, points.len() / 2);
+    call(
+        "bn128Pairing",
+        "i32 i32",
+        &args,
+        &points.concat(),
+        ticks,
+        costs,
+    )
+}
+
+#[test]
+fn add() {
+    assert_eq!(point_op("bn128Add", G1, G1), (1, TWO_G1.to_string()));
+    assert_eq!(point_op("bn128Add", G1, MINUS_G1), (1, "00".repeat(64)));
+    assert_eq!(
+        point_op("bn128Add", G1, &"00".repeat(64)),
+        (1, G1.to_string())
+    );
+}
+
+#[test]
+fn mul() {
+    let two = format!("{:064x}", 2);
+    assert_eq!(point_op("bn128Mul", G1, &two), (1, TWO_G1.to_string()));
+    assert_eq!(point_op("bn128Mul", G1, ORDER), (1, "00".repeat(64)));
+    // Scalars are not required to be reduced.
+    assert_eq!(point_op("bn128Mul", G1, &"ff".repeat(32)).0, 1);
+}
+
+#[test]
+fn invalid_points_fail() {
+    let not_on_curve = format!("{:064x}{:064x}", 1, 1);
+    assert_eq!(
+        point_op("bn128Add", G1, &not_on_curve),
+        (0, "00".repeat(64))
+    );
+    assert_eq!(point_op("bn128Mul", &not_on_curve, G1).0, 0);
+    let unreduced = format!("{}{:064x}", ORDER.replace("30644", "40644"), 2);
+    assert_eq!(point_op("bn128Add", &unreduced, G1).0, 0);
+}
+
+#[test]
+fn pairing_check() {
+    let check = |points: &[&str]| {
+        pairing(points, DEFAULT_TICKS, &TickCosts::default())
+            .unwrap()
+            .result
+    };
+    assert_eq!(check(&[]), 1);
+    assert_eq!(check(&[G1, G2, MINUS_G1, G2]), 1);
+    assert_eq!(check(&[G1, G2, G1, G2]), 0);
+    assert_eq!(check(&[G1, &"00".repeat(128)]), 1);
+    let invalid_g2 = format!("{}{:064x}", &G2[..192], 1);
+    assert_eq!(check(&[G1, &invalid_g2]), -1);
+}
+
+#[test]
+fn pairing_is_charged_per_pair() {
+    let costs = TickCosts {
+        bn128_pairing: PairingCost {
+            base: 100,
+            per_pair: 10,
+        },
+        ..TickCosts::disabled()
+    };
+    assert_charges(120, |ticks| pairing(&[G1, G2, MINUS_G1, G2], ticks, &costs));
+}
+
+#[test]
+fn huge_pair_count_runs_out_of_ticks() {
+    assert!(matches!(
+        call(
+            "bn128Pairing",
+            "i32 i32",
+            &format!("(i32.const {}) (i32.const -1)", INPUT),
+            "",
+            DEFAULT_TICKS,
+            &TickCosts::default()
+        ),
+        Err(ScoutError::OutOfTicks)
+    ));
+}
@@ -0,0 +1,138 @@
+//! Helpers shared by the integration tests for running WebAssembly text scripts and laying out
+//! their inputs.
+
+// Each test crate only uses some of the helpers.
+#![allow(dead_code)]
+
+use phase2_scout::types::Bytes32;
+use phase2_scout::{
+    execute_code, ExecutionResult, ScoutError, ShardBlockBody, TickCosts, DEFAULT_TICKS,
+};
+use rustc_hex::FromHex;
+
+/// Compiles `source` and executes it with the given pre-state, block data, tick budget and costs.
+pub fn execute_wat(
+    source: &str,
+    pre_state: &Bytes32,
+    data: &[u8],
+    ticks: u32,
+    costs: &TickCosts,
+) -> Result<ExecutionResult, ScoutError> {
+    let code = wat::parse_str(source).expect("valid wat");
+    let block_data = ShardBlockBody {
+        data: data.to_vec(),
+    };
+    execute_code(&code, &[], pre_state, &block_data, ticks, costs)
+}
+
+/// Executes `source` without block data, starting from the zero state.
+pub fn run_wat(source: &str, ticks: u32, costs: &TickCosts) -> Result<ExecutionResult, ScoutError> {
+    execute_wat(source, &Bytes32::default(), &[], ticks, costs)
+}
+
+/// Executes `source` with the given block data and the default tick budget and costs.
+pub fn run_with_data(source: &str, data: &[u8]) -> Result<ExecutionResult, ScoutError> {
+    execute_wat(
+        source,
+        &Bytes32::default(),
+        data,
+        DEFAULT_TICKS,
+        &TickCosts::default(),
+    )
+}
+
+/// The number of bytes at the start of memory returned by `call_host` as the output of the host
+/// function. Inputs are placed after them, from `INPUT` on.
+pub const OUTPUT_SIZE: u32 = 256;
+
+/// Where `call_host` tests place the inputs of host functions.
+pub const INPUT: u32 = 1024;
+
+/// The outcome of a host function called by `call_host`.
+#[derive(Debug)]
+pub struct HostCall {
+    /// The value returned by the host function, or 0 if it does not return one.
+    pub result: i32,
+    /// The first `OUTPUT_SIZE` bytes of memory after the call.
+    pub output: Vec<u8>,
+    pub ticks_used: u32,
+}
+
+/// Calls `namespace.function`, which has the given `signature` such as `(param i32) (result i32)`,
+/// with the `args` instructions after placing the bytes of each segment at its offset.
+///
+/// The host function writes its output to the start of memory; the module then stores the value
+/// it returned after the output and pushes both as a deposit.
+pub fn call_host(
+    namespace: &str,
+    function: &str,
+    signature: &str,
+    args: &str,
+    segments: &[(u32, &[u8])],
+    ticks: u32,
+    costs: &TickCosts,
+) -> Result<HostCall, ScoutError> {
+    let call = format!("(call $f {})", args);
+    let call = if signature.contains("result") {
+        format!("(i32.store (i32.const {}) {})", OUTPUT_SIZE, call)
+    } else {
+        call
+    };
+    let data: String = segments
+        .iter()
+        .map(|(offset, bytes)| format!("(data (i32.const {}) \"{}\")", offset, wat_bytes(bytes)))
+        .collect();
+    let source = format!(
+        r#"(module
+            (import "{}" "{}" (func $f {}))
+            (import "eth2" "pushNewDeposit" (func $push (param i32 i32)))
+            (memory (export "memory") 1)
+            {}
+            (func (export "main") {} (call $push (i32.const 0) (i32.const {}))))"#,
+        namespace,
+        function,
+        signature,
+        data,
+        call,
+        OUTPUT_SIZE + 4
+    );
+    let result = run_wat(&source, ticks, costs)?;
+    let mut deposit = result.deposits[0].clone();
+    let returned = deposit.split_off(OUTPUT_SIZE as usize);
+    Ok(HostCall {
+        result: i32::from_le_bytes([returned[0], returned[1], returned[2], returned[3]]),
+        output: deposit,
+        ticks_used: result.ticks_used,
+    })
+}
+
+/// Checks that `call`, given a tick budget, uses exactly `ticks` ticks: it succeeds with that
+/// budget and runs out of ticks with one tick less. Metering should be disabled for `call` so that
+/// only the host function is charged.
+pub fn assert_charges(ticks: u32, call: impl Fn(u32) -> Result<HostCall, ScoutError>) {
+    assert_eq!(call(ticks).expect("successful call").ticks_used, ticks);
+    assert!(matches!(call(ticks - 1), Err(ScoutError::OutOfTicks)));
+}
+
+/// Escapes `bytes` for a data segment string.
+pub fn wat_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("\\{:02x}", byte)).collect()
+}
+
+/// Escapes the bytes encoded by the `hex` string for a data segment string.
+pub fn wat_hex(hex: &str) -> String {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| format!("\\{}", std::str::from_utf8(pair).unwrap()))
+        .collect()
+}
+
+/// Decodes a hex string.
+pub fn from_hex(hex: &str) -> Vec<u8> {
+    hex.from_hex().expect("valid hex")
+}
+
+/// Formats `bytes` as lowercase hex without a prefix.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use common::{assert_charges, call_host, to_hex, HostCall, INPUT};
+use phase2_scout::{HashCost, ScoutError, TickCosts, DEFAULT_TICKS};
+
+/// Hashes the first `length` bytes of `input` with `crypto.<function>`.
+fn hash(
+    function: &str,
+    input: &str,
+    length: i32,
+    ticks: u32,
+    costs: &TickCosts,
+) -> Result<HostCall, ScoutError> {
+    call_host(
+        "crypto",
+        function,
+        "(param i32 i32 i32)",
+        &format!("(i32.const {}) (i32.const {}) (i32.const 0)", INPUT, length),
+        &[(INPUT, input.as_bytes())],
+        ticks,
+        costs,
+    )
+}
+
+fn digest(function: &str, input: &str) -> String {
+    let call = hash(
+        function,
+        input,
+        input.len() as i32,
+        DEFAULT_TICKS,
+        &TickCosts::default(),
+    )
+    .expect("successful execution");
+    to_hex(&call.output[..32])
+}
+
+#[test]
+fn keccak256() {
+    assert_eq!(
+        digest("keccak256", ""),
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+    );
+    assert_eq!(
+        digest("keccak256", "abc"),
+        "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+    );
+}
+
+#[test]
+fn sha256() {
+    assert_eq!(
+        digest("sha256", ""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        digest("sha256", "abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+#[test]
+fn hashing_is_charged_per_byte() {
+    let costs = TickCosts {
+        keccak256: HashCost {
+            base: 10,
+            per_byte: 3,
+        },
+        ..TickCosts::disabled()
+    };
+    assert_charges(310, |ticks| hash("keccak256", "", 100, ticks, &costs));
+}
+
+#[test]
+fn huge_input_runs_out_of_ticks() {
+    assert!(matches!(
+        hash("sha256", "", -1, DEFAULT_TICKS, &TickCosts::default()),
+        Err(ScoutError::OutOfTicks)
+    ));
+}
+
+#[test]
+fn input_out_of_bounds() {
+    let costs = TickCosts {
+        sha256: HashCost {
+            base: 0,
+            per_byte: 0,
+        },
+        ..TickCosts::default()
+    };
+    assert!(matches!(
+        hash("sha256", "", 65536, DEFAULT_TICKS, &costs),
+        Err(ScoutError::HostCall { .. })
+    ));
+}
+
+/// Recovers the signer of `hash` with `crypto.ecrecover`, which writes the address to 0.
+fn call_ecrecover(hash: &[u8; 32], signature: &[u8], ticks: u32) -> Result<HostCall, ScoutError> {
+    call_host(
+        "crypto",
+        "ecrecover",
+        "(param i32 i32 i32) (result i32)",
+        &format!(
+            "(i32.const {}) (i32.const {}) (i32.const 0)",
+            INPUT,
+            INPUT + 64
+        ),
+        &[(INPUT, hash), (INPUT + 64, signature)],
+        ticks,
+        &TickCosts::disabled(),
+    )
+}
+
+/// Returns the result code and the address written by `crypto.ecrecover`.
+fn ecrecover(hash: &[u8; 32], signature: &[u8]) -> (i32, String) {
+    let call = call_ecrecover(hash, signature, DEFAULT_TICKS).expect("successful execution");
+    (call.result, to_hex(&call.output[..20]))
+}
+
+/// Signs `hash` with the secret key 1, returning `r || s || v`.
+fn sign(hash: &[u8; 32]) -> Vec<u8> {
+    let mut secret = [0u8; 32];
+    secret[31] = 1;
+    let (signature, recovery_id) = secp256k1::sign(
+        &secp256k1::Message::parse(hash),
+        &secp256k1::SecretKey::parse(&secret).unwrap(),
+    );
+    let mut signature = signature.serialize().to_vec();
+    signature.push(27 + recovery_id.serialize());
+    signature
+}
+
+/// The address of the secret key 1.
+const ADDRESS: &str = "7e5f4552091a69125d5dfcb7b8c2659029395bdf";
+
+#[test]
+fn ecrecover_address() {
+    let hash = [0x42; 32];
+    let mut signature = sign(&hash);
+    assert_eq!(ecrecover(&hash, &signature), (1, ADDRESS.to_string()));
+    assert_charges(3000, |ticks| call_ecrecover(&hash, &signature, ticks));
+
+    // Recovery ids 0 and 1 are accepted too.
+    signature[64] -= 27;
+    assert_eq!(ecrecover(&hash, &signature), (1, ADDRESS.to_string()));
+
+    // The wrong recovery id yields a different key.
+    signature[64] ^= 1;
+    assert_ne!(ecrecover(&hash, &signature), (1, ADDRESS.to_string()));
+}
+
+#[test]
+fn ecrecover_invalid_signature() {
+    let hash = [0x42; 32];
+    let signature = sign(&hash);
+    let invalid = |modify: &dyn Fn(&mut Vec<u8>)| {
+        let mut signature = signature.clone();
+        modify(&mut signature);
+        ecrecover(&hash, &signature)
+    };
+    let failure = (0, "00".repeat(20));
+    assert_eq!(invalid(&|signature| signature[64] = 29), failure);
+    assert_eq!(
+        invalid(&|signature| signature[32..64].copy_from_slice(&[0; 32])),
+        failure
+    );
+    assert_eq!(
+        invalid(&|signature| signature[0..32].copy_from_slice(&[0xff; 32])),
+        failure
+    );
+}
+
+#[test]
+fn ecrecover_out_of_bounds() {
+    assert!(matches!(
+        call_host(
+            "crypto",
+            "ecrecover",
+            "(param i32 i32 i32) (result i32)",
+            "(i32.const 0) (i32.const 65500) (i32.const 0)",
+            &[],
+            DEFAULT_TICKS,
+            &TickCosts::default(),
+        ),
+        Err(ScoutError::HostCall { .. })
+    ));
+}
@@ -0,0 +1,240 @@
+//! Hostile execution scripts: every one of these must fail with an error instead of panicking the
+//! runner.
+
+mod common;
+
+use common::run_with_data as run;
+use phase2_scout::types::Bytes32;
+use phase2_scout::{ExecutionResult, ScoutError};
+
+fn run_main(imports: &str, body: &str) -> Result<ExecutionResult, ScoutError> {
+    run(
+        &format!(
+            r#"(module
+                {}
+                (memory (export "memory") 1)
+                (func (export "main") {}))"#,
+            imports, body
+        ),
+        &[1, 2, 3, 4],
+    )
+}
+
+fn assert_host_call_failure(ret: Result<ExecutionResult, ScoutError>, expected_function: &str) {
+    match ret {
+        Err(ScoutError::HostCall { function, .. }) => assert_eq!(function, expected_function),
+        other => panic!("expected failure of {}, got {:?}", expected_function, other),
+    }
+}
+
+#[test]
+fn load_pre_state_root_out_of_bounds() {
+    let ret = run_main(
+        r#"(import "eth2" "loadPreStateRoot" (func $load (param i32)))"#,
+        "(call $load (i32.const 65520))",
+    );
+    assert_host_call_failure(ret, "eth2_loadPreStateRoot");
+}
+
+#[test]
+fn save_post_state_root_at_address_space_end() {
+    let ret = run_main(
+        r#"(import "eth2" "savePostStateRoot" (func $save (param i32)))"#,
+        "(call $save (i32.const -1))",
+    );
+    assert_host_call_failure(ret, "eth2_savePostStateRoot");
+}
+
+#[test]
+fn block_data_copy_out_of_bounds() {
+    let ret = run_main(
+        r#"(import "eth2" "blockDataCopy" (func $copy (param i32 i32 i32)))"#,
+        "(call $copy (i32.const 0) (i32.const 2) (i32.const 1000))",
+    );
+    assert_host_call_failure(ret, "eth2_blockDataCopy");
+}
+
+#[test]
+fn block_data_copy_to_invalid_memory() {
+    let ret = run_main(
+        r#"(import "eth2" "blockDataCopy" (func $copy (param i32 i32 i32)))"#,
+        "(call $copy (i32.const 65535) (i32.const 0) (i32.const 4))",
+    );
+    assert_host_call_failure(ret, "eth2_blockDataCopy");
+}
+
+#[test]
+fn push_new_deposit_huge_length() {
+    let ret = run_main(
+        r#"(import "eth2" "pushNewDeposit" (func $push (param i32 i32)))"#,
+        "(call $push (i32.const 0) (i32.const -1))",
+    );
+    assert_host_call_failure(ret, "eth2_pushNewDeposit");
+}
+
+#[test]
+fn debug_print_mem_out_of_bounds() {
+    let ret = run_main(
+        r#"(import "debug" "printMem" (func $print (param i32 i32)))"#,
+        "(call $print (i32.const 65530) (i32.const 10))",
+    );
+    assert_host_call_failure(ret, "debug_printMem");
+}
+
+#[test]
+fn debug_print_mem_hex_out_of_bounds() {
+    let ret = run_main(
+        r#"(import "debug" "printMemHex" (func $print (param i32 i32)))"#,
+        "(call $print (i32.const 0) (i32.const -1))",
+    );
+    assert_host_call_failure(ret, "debug_printMemHex");
+}
+
+#[test]
+fn env_namespace_out_of_bounds() {
+    let ret = run_main(
+        r#"(import "env" "eth2_loadPreStateRoot" (func $load (param i32)))"#,
+        "(call $load (i32.const 65536))",
+    );
+    assert_host_call_failure(ret, "eth2_loadPreStateRoot");
+}
+
+#[test]
+fn bignum_add256_overflow() {
+    let ret = run(
+        r#"(module
+            (import "bignum" "add256" (func $add (param i32 i32 i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff\ff")
+            (func (export "main") (call $add (i32.const 0) (i32.const 0) (i32.const 64))))"#,
+        &[],
+    );
+    assert_host_call_failure(ret, "bignum_add256");
+}
+
+#[test]
+fn bignum_sub256_underflow() {
+    let ret = run(
+        r#"(module
+            (import "bignum" "sub256" (func $sub (param i32 i32 i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 63) "\01")
+            (func (export "main") (call $sub (i32.const 0) (i32.const 32) (i32.const 64))))"#,
+        &[],
+    );
+    assert_host_call_failure(ret, "bignum_sub256");
+}
+
+#[test]
+fn bignum_operand_out_of_bounds() {
+    let ret = run_main(
+        r#"(import "bignum" "add256" (func $add (param i32 i32 i32)))"#,
+        "(call $add (i32.const 0) (i32.const 65535) (i32.const 0))",
+    );
+    assert_host_call_failure(ret, "bignum_add256");
+}
+
+#[test]
+fn bignum_result_out_of_bounds() {
+    let ret = run_main(
+        r#"(import "bignum" "add256" (func $add (param i32 i32 i32)))"#,
+        "(call $add (i32.const 0) (i32.const 0) (i32.const 65535))",
+    );
+    assert_host_call_failure(ret, "bignum_add256");
+}
+
+#[test]
+fn use_ticks_exhausted() {
+    let ret = run_main(
+        r#"(import "eth2" "useTicks" (func $use (param i32)))"#,
+        "(loop $forever (call $use (i32.const 1000000)) (br $forever))",
+    );
+    assert!(matches!(ret, Err(ScoutError::OutOfTicks)));
+}
+
+#[test]
+fn unreachable() {
+    let ret = run_main("", "unreachable");
+    assert!(matches!(ret, Err(ScoutError::Trap(_))));
+}
+
+#[test]
+fn infinite_recursion() {
+    let ret = run(
+        r#"(module
+            (memory (export "memory") 1)
+            (func $main (export "main") (call $main)))"#,
+        &[],
+    );
+    assert!(matches!(ret, Err(ScoutError::Trap(_))));
+}
+
+#[test]
+fn unknown_import() {
+    let ret = run_main(r#"(import "eth2" "selfDestruct" (func $f))"#, "");
+    assert!(matches!(ret, Err(ScoutError::Link(_))));
+}
+
+#[test]
+fn mismatching_import_signature() {
+    let ret = run_main(
+        r#"(import "eth2" "blockDataSize" (func $size (param i64)))"#,
+        "",
+    );
+    assert!(matches!(ret, Err(ScoutError::Link(_))));
+}
+
+#[test]
+fn missing_main() {
+    let ret = run(r#"(module (memory (export "memory") 1))"#, &[]);
+    assert!(matches!(ret, Err(ScoutError::Link(_))));
+}
+
+#[test]
+fn main_with_parameters() {
+    let ret = run(
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "main") (param i32)))"#,
+        &[],
+    );
+    assert!(matches!(ret, Err(ScoutError::Trap(_))));
+}
+
+#[test]
+fn memory_export_is_not_memory() {
+    let ret = run(
+        r#"(module
+            (memory 1)
+            (func (export "memory"))
+            (func (export "main")))"#,
+        &[],
+    );
+    assert!(matches!(ret, Err(ScoutError::Link(_))));
+}
+
+#[test]
+fn host_call_from_start_function() {
+    let ret = run(
+        r#"(module
+            (import "eth2" "loadPreStateRoot" (func $load (param i32)))
+            (memory (export "memory") 1)
+            (func $start (call $load (i32.const 0)))
+            (start $start)
+            (func (export "main")))"#,
+        &[],
+    );
+    assert!(ret.is_err());
+}
+
+#[test]
+fn well_behaved_script() {
+    let ret = run_main(
+        r#"(import "eth2" "loadPreStateRoot" (func $load (param i32)))
+           (import "eth2" "savePostStateRoot" (func $save (param i32)))"#,
+        "(call $load (i32.const 65504)) (call $save (i32.const 65504))",
+    );
+    let result = ret.expect("successful execution");
+    assert_eq!(result.post_state, Bytes32::default());
+    assert!(result.deposits.is_empty());
+}
@@ -0,0 +1,96 @@
+use phase2_scout::output::{junit, tap};
+use phase2_scout::report::{BlockReport, CheckResult, TestReport};
+use phase2_scout::runner::Summary;
+use phase2_scout::testfile::BlockOutcome;
+use phase2_scout::ScoutError;
+
+fn block(index: usize, outcome: BlockOutcome, error: Option<&str>) -> BlockReport {
+    BlockReport {
+        index,
+        env: 0,
+        outcome: Some(outcome),
+        error: error.map(str::to_string),
+        ticks_used: None,
+        ticks_left: None,
+        storage_loads: 0,
+        storage_stores: 0,
+        post_state: None,
+    }
+}
+
+fn summary() -> Summary {
+    let mut failing = TestReport::new("failing.yaml");
+    failing.blocks = vec![
+        block(0, BlockOutcome::Success, None),
+        block(1, BlockOutcome::Trap, Some("Trap: Unreachable")),
+        block(2, BlockOutcome::Success, None),
+    ];
+    failing.checks = vec![
+        CheckResult::new("block 2", vec!["expected trap, got success".to_string()]),
+        CheckResult::new("deposit receipts", vec![]),
+        CheckResult::new("state", vec!["expected <01>, got <02>".to_string()]),
+    ];
+    let mut aborted = TestReport::new("aborted.yaml");
+    aborted.blocks = vec![
+        block(0, BlockOutcome::Success, None),
+        block(1, BlockOutcome::Trap, Some("Trapped: Unreachable")),
+    ];
+    aborted.error = Some("Trapped: Unreachable".to_string());
+    Summary {
+        tests: vec![
+            failing,
+            aborted,
+            TestReport::from_error(
+                "broken.yaml",
+                &ScoutError::InvalidInput("no such environment".to_string()),
+            ),
+        ],
+        seconds: 0.5,
+    }
+}
+
+#[test]
+fn junit_output() {
+    let xml = junit(&summary());
+    assert!(xml.contains(r#"<testsuites tests="8" failures="2" errors="2" time="0.500">"#));
+    assert!(xml.contains(
+        r#"<testsuite name="failing.yaml" tests="5" failures="2" errors="0" skipped="1""#
+    ));
+    assert!(xml.contains(r#"<testcase name="block 0" classname="failing.yaml"/>"#));
+    assert!(xml.contains(r#"<skipped message="trap: Trap: Unreachable"/>"#));
+    assert!(xml.contains(r#"<failure message="expected &lt;01&gt;, got &lt;02&gt;">"#));
+    assert!(xml.contains(
+        r#"<testsuite name="aborted.yaml" tests="2" failures="0" errors="1" skipped="0""#
+    ));
+    assert!(xml.contains(
+        r#"<testcase name="block 1" classname="aborted.yaml">
+      <error message="Trapped: Unreachable">"#
+    ));
+    assert!(xml.contains(r#"<testcase name="run" classname="broken.yaml">"#));
+    assert!(xml.contains(r#"<error message="Invalid input: no such environment">"#));
+}
+
+#[test]
+fn tap_output() {
+    let output = tap(&summary());
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        &lines[..4],
+        &[
+            "TAP version 13",
+            "1..3",
+            "# Subtest: failing.yaml",
+            "    1..5"
+        ]
+    );
+    assert!(lines.contains(&"    ok 1 - block 0"));
+    assert!(lines.contains(&"    ok 2 - block 1 # SKIP trap: Trap: Unreachable"));
+    assert!(lines.contains(&"    not ok 3 - block 2"));
+    assert!(lines.contains(&"        - \"expected trap, got success\""));
+    assert!(lines.contains(&"    ok 4 - deposit receipts"));
+    assert!(lines.contains(&"    not ok 5 - state"));
+    assert!(lines.contains(&"not ok 1 - failing.yaml # time=0.000s"));
+    assert!(lines.contains(&"    not ok 2 - block 1"));
+    assert!(lines.contains(&"not ok 2 - aborted.yaml # time=0.000s"));
+    assert!(lines.contains(&"not ok 3 - broken.yaml # time=0.000s"));
+}
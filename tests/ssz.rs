@@ -0,0 +1,141 @@
+mod common;
+
+use common::{assert_charges, call_host, HostCall, INPUT};
+use phase2_scout::{MerkleCost, ScoutError, TickCosts, DEFAULT_TICKS};
+use sha2::{Digest, Sha256};
+
+fn hash_pair(left: &[u8], right: &[u8]) -> Vec<u8> {
+    Sha256::digest(&[left, right].concat()).to_vec()
+}
+
+/// Computes the hash tree root of `data` with `ssz.hashTreeRoot`, which writes it to 0.
+fn hash_tree_root(data: &[u8], ticks: u32, costs: &TickCosts) -> Result<HostCall, ScoutError> {
+    call_host(
+        "ssz",
+        "hashTreeRoot",
+        "(param i32 i32 i32)",
+        &format!(
+            "(i32.const {}) (i32.const {}) (i32.const 0)",
+            INPUT,
+            data.len()
+        ),
+        &[(INPUT, data)],
+        ticks,
+        costs,
+    )
+}
+
+/// Where the root, the indices, the leaves and the proof of a multiproof are placed.
+const ROOT: u32 = INPUT;
+const INDICES: u32 = INPUT + 1024;
+const LEAVES: u32 = INPUT + 2048;
+const PROOF: u32 = INPUT + 4096;
+
+/// Verifies a multiproof against `root`, returning the result of `ssz.verifyMultiproof`.
+fn verify_multiproof(
+    root: &[u8],
+    indices: &[u64],
+    leaves: &[&[u8]],
+    proof: &[&[u8]],
+) -> Result<i32, ScoutError> {
+    let indices: Vec<u8> = indices
+        .iter()
+        .flat_map(|index| index.to_le_bytes().to_vec())
+        .collect();
+    let args: Vec<String> = [
+        ROOT,
+        INDICES,
+        LEAVES,
+        leaves.len() as u32,
+        PROOF,
+        proof.len() as u32,
+    ]
+    .iter()
+    .map(|arg| format!("(i32.const {})", arg))
+    .collect();
+    let call = call_host(
+        "ssz",
+        "verifyMultiproof",
+        "(param i32 i32 i32 i32 i32 i32) (result i32)",
+        &args.join(" "),
+        &[
+            (ROOT, root),
+            (INDICES, &indices),
+            (LEAVES, &leaves.concat()),
+            (PROOF, &proof.concat()),
+        ],
+        DEFAULT_TICKS,
+        &TickCosts::default(),
+    )?;
+    Ok(call.result)
+}
+
+#[test]
+fn hash_tree_root_of_chunks() {
+    let data: Vec<u8> = (0..96u8).collect();
+    let expected = hash_pair(
+        &hash_pair(&data[0..32], &data[32..64]),
+        &hash_pair(&data[64..96], &[0; 32]),
+    );
+    let call = hash_tree_root(&data, DEFAULT_TICKS, &TickCosts::default()).unwrap();
+    assert_eq!(call.output[..32].to_vec(), expected);
+}
+
+#[test]
+fn hash_tree_root_is_charged_per_chunk() {
+    let costs = TickCosts {
+        ssz_hash_tree_root: MerkleCost {
+            base: 10,
+            per_chunk: 100,
+        },
+        ..TickCosts::disabled()
+    };
+    assert_charges(310, |ticks| hash_tree_root(&[1; 65], ticks, &costs));
+}
+
+#[test]
+fn multiproof() {
+    let leaves: Vec<[u8; 32]> = (1..=4u8).map(|byte| [byte; 32]).collect();
+    let left = hash_pair(&leaves[0], &leaves[1]);
+    let right = hash_pair(&leaves[2], &leaves[3]);
+    let root = hash_pair(&left, &right);
+
+    assert_eq!(
+        verify_multiproof(
+            &root,
+            &[6, 5],
+            &[&leaves[2], &leaves[1]],
+            &[&leaves[3], &leaves[0]]
+        )
+        .unwrap(),
+        1
+    );
+    assert_eq!(
+        verify_multiproof(&root, &[6], &[&leaves[2]], &[&leaves[3], &left]).unwrap(),
+        1
+    );
+    assert_eq!(
+        verify_multiproof(&root, &[6], &[&leaves[3]], &[&leaves[3], &left]).unwrap(),
+        0
+    );
+    assert_eq!(
+        verify_multiproof(&root, &[6], &[&leaves[2]], &[&leaves[3]]).unwrap(),
+        0
+    );
+}
+
+#[test]
+fn huge_leaf_count_runs_out_of_ticks() {
+    assert!(matches!(
+        call_host(
+            "ssz",
+            "verifyMultiproof",
+            "(param i32 i32 i32 i32 i32 i32) (result i32)",
+            "(i32.const 0) (i32.const 0) (i32.const 0) (i32.const -1) (i32.const 0) (i32.const -1)",
+            &[],
+            DEFAULT_TICKS,
+            &TickCosts::default()
+        ),
+        Err(ScoutError::OutOfTicks)
+    ));
+}
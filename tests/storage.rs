@@ -0,0 +1,128 @@
+use phase2_scout::types::Bytes32;
+use phase2_scout::{
+    execute_code_with_storage, process_shard_block, BeaconState, ExecutionScript, ScoutError,
+    ShardBlock, ShardBlockBody, ShardState, Storage, StorageAccesses, TickCosts, DEFAULT_TICKS,
+};
+
+/// Increments the first byte of the value stored under the key of all ones, and stores a copy
+/// of it under the key of all twos.
+const COUNTER: &str = r#"(module
+    (import "eth2" "storageLoad" (func $load (param i32 i32)))
+    (import "eth2" "storageStore" (func $store (param i32 i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 0) "\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01")
+    (data (i32.const 32) "\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02")
+    (func (export "main")
+        (call $load (i32.const 0) (i32.const 64))
+        (i32.store8 (i32.const 64) (i32.add (i32.load8_u (i32.const 64)) (i32.const 1)))
+        (call $store (i32.const 0) (i32.const 64))
+        (call $store (i32.const 32) (i32.const 64))))"#;
+
+fn key(byte: u8) -> Bytes32 {
+    Bytes32::from([byte; 32])
+}
+
+fn counter(value: u8) -> Bytes32 {
+    let mut counter = Bytes32::default();
+    counter.bytes[0] = value;
+    counter
+}
+
+#[test]
+fn load_and_store() {
+    let code = wat::parse_str(COUNTER).unwrap();
+    let result = execute_code_with_storage(
+        &code,
+        &[],
+        &Bytes32::default(),
+        &Storage::new(),
+        &ShardBlockBody::default(),
+        DEFAULT_TICKS,
+        &TickCosts::disabled(),
+    )
+    .unwrap();
+    let expected: Storage = vec![(key(1), counter(1)), (key(2), counter(1))]
+        .into_iter()
+        .collect();
+    assert_eq!(result.post_storage, expected);
+    assert_eq!(
+        result.storage_accesses,
+        StorageAccesses {
+            loads: 1,
+            stores: 2
+        }
+    );
+    let costs = TickCosts::default();
+    assert_eq!(
+        result.ticks_used,
+        costs.storage_load + 2 * costs.storage_store
+    );
+}
+
+#[test]
+fn storing_zero_removes_the_key() {
+    let code = wat::parse_str(COUNTER).unwrap();
+    let storage: Storage = vec![(key(1), counter(255)), (key(3), counter(3))]
+        .into_iter()
+        .collect();
+    let result = execute_code_with_storage(
+        &code,
+        &[],
+        &Bytes32::default(),
+        &storage,
+        &ShardBlockBody::default(),
+        DEFAULT_TICKS,
+        &TickCosts::disabled(),
+    )
+    .unwrap();
+    let expected: Storage = vec![(key(3), counter(3))].into_iter().collect();
+    assert_eq!(result.post_storage, expected);
+}
+
+#[test]
+fn shard_blocks_update_storage() {
+    let beacon_state = BeaconState {
+        execution_scripts: vec![ExecutionScript {
+            code: wat::parse_str(COUNTER).unwrap(),
+        }],
+        libraries: Vec::new(),
+    };
+    let mut state = ShardState {
+        exec_env_states: vec![Bytes32::default()],
+        ..Default::default()
+    };
+    for _ in 0..3 {
+        let block = ShardBlock {
+            env: 0,
+            data: ShardBlockBody::default(),
+        };
+        let result = process_shard_block(
+            &mut state,
+            &beacon_state,
+            Some(block),
+            DEFAULT_TICKS,
+            &TickCosts::default(),
+        )
+        .unwrap();
+        assert_eq!(result.storage_accesses.loads, 1);
+    }
+    assert_eq!(state.exec_env_storage[0].get(&key(1)), Some(&counter(3)));
+}
+
+#[test]
+fn failed_execution_discards_storage_changes() {
+    let code = wat::parse_str(COUNTER).unwrap();
+    let costs = TickCosts::disabled();
+    assert!(matches!(
+        execute_code_with_storage(
+            &code,
+            &[],
+            &Bytes32::default(),
+            &Storage::new(),
+            &ShardBlockBody::default(),
+            costs.storage_load + costs.storage_store,
+            &costs,
+        ),
+        Err(ScoutError::OutOfTicks)
+    ));
+}
@@ -0,0 +1,562 @@
+//! Runs complete test files, with the scripts they reference written to a temporary directory.
+
+use phase2_scout::runner::{collect_tests, run_tests};
+use phase2_scout::testfile::{
+    check_deposits, encode_ssz, fill_test, load_test_file, BlockOutcome, TestFormat, TestOptions,
+    TestSszField,
+};
+use phase2_scout::{process_yaml_test, BLSPubKey, Deposit, ScoutError, TestReport, TickCosts};
+use rustc_hex::ToHex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Saves its block data as the post state, trapping if the block is empty.
+const SAVE_BLOCK: &str = r#"(module
+    (import "eth2" "blockDataSize" (func $size (result i32)))
+    (import "eth2" "blockDataCopy" (func $copy (param i32 i32 i32)))
+    (import "eth2" "savePostStateRoot" (func $save (param i32)))
+    (memory (export "memory") 1)
+    (func (export "main")
+        (if (i32.eqz (call $size)) (then unreachable))
+        (call $copy (i32.const 0) (i32.const 0) (i32.const 32))
+        (call $save (i32.const 0))))"#;
+
+/// Creates a fresh directory for the test named `name` holding `save_block.wasm` and a test file
+/// with the given content, returning the path of the test file.
+fn write_test(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("scout-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("save_block.wasm");
+    fs::write(&script, wat::parse_str(SAVE_BLOCK).unwrap()).unwrap();
+    let test = dir.join("test.yaml");
+    fs::write(&test, content.replace("SCRIPT", script.to_str().unwrap())).unwrap();
+    test
+}
+
+fn run(test: &Path, options: &TestOptions) -> Result<TestReport, ScoutError> {
+    process_yaml_test(test.to_str().unwrap(), options)
+}
+
+const FAILING_BLOCK: &str = r#"
+beacon_state:
+  execution_scripts:
+    - SCRIPT
+shard_pre_state:
+  exec_env_states:
+    - "0000000000000000000000000000000000000000000000000000000000000000"
+shard_blocks:
+  - env: 0
+    data: "0101010101010101010101010101010101010101010101010101010101010101"
+  - env: 0
+    data: ""
+  - env: 0
+    data: "0202020202020202020202020202020202020202020202020202020202020202"
+shard_post_state:
+  exec_env_states:
+    - "0202020202020202020202020202020202020202020202020202020202020202"
+deposit_receipts: []
+"#;
+
+/// The index of the block at which the test file was stopped and the error which stopped it.
+fn stopped_at(report: TestReport) -> (usize, String) {
+    let block = report.blocks.last().expect("a block report");
+    assert!(block.error.is_some());
+    (block.index, report.error.expect("an error"))
+}
+
+#[test]
+fn failing_block_aborts_by_default() {
+    let test = write_test("failing-block-aborts", FAILING_BLOCK);
+    let report = run(&test, &TestOptions::default()).unwrap();
+    assert_eq!(report.blocks.len(), 2);
+    assert_eq!(report.blocks[1].outcome, Some(BlockOutcome::Trap));
+    let (index, error) = stopped_at(report);
+    assert_eq!(index, 1);
+    assert!(error.starts_with("Trapped"));
+}
+
+#[test]
+fn failing_block_is_skipped_when_tolerated() {
+    let test = write_test("failing-block-skipped", FAILING_BLOCK);
+    let options = TestOptions {
+        tolerate_failures: true,
+        ..Default::default()
+    };
+    assert!(run(&test, &options).unwrap().passed());
+
+    let test = write_test(
+        "failing-block-skipped-by-file",
+        &format!("tolerate_failures: true\n{}", FAILING_BLOCK),
+    );
+    assert!(run(&test, &TestOptions::default()).unwrap().passed());
+}
+
+#[test]
+fn runner_errors_are_not_tolerated() {
+    let test = write_test(
+        "unknown-environment",
+        &FAILING_BLOCK.replace("  - env: 0\n    data: \"\"", "  - env: 1\n    data: \"\""),
+    );
+    let options = TestOptions {
+        tolerate_failures: true,
+        ..Default::default()
+    };
+    let report = run(&test, &options).unwrap();
+    assert_eq!(report.blocks[1].outcome, None);
+    assert_eq!(report.blocks[1].post_state, None);
+    let (index, error) = stopped_at(report);
+    assert_eq!(index, 1);
+    assert!(error.starts_with("Invalid input"));
+}
+
+#[test]
+fn expected_failures_do_not_abort() {
+    let test = write_test(
+        "expected-failures",
+        &FAILING_BLOCK
+            .replace(
+                "    data: \"\"\n",
+                "    data: \"\"\n    expect:\n      result: trap\n      error: Unreachable\n      post_state: \"0101010101010101010101010101010101010101010101010101010101010101\"\n",
+            )
+            .replace(
+                "  - env: 0\n    data: \"02",
+                "  - env: 0\n    ticks: 1\n    expect:\n      result: out_of_ticks\n    data: \"03\"\n  - env: 0\n    data: \"02",
+            ),
+    );
+    assert!(run(&test, &TestOptions::default()).unwrap().passed());
+}
+
+#[test]
+fn invalid_expectation_is_an_error() {
+    let test = write_test(
+        "invalid-expectation",
+        &FAILING_BLOCK.replace(
+            "    data: \"\"\n",
+            "    data: \"\"\n    expect:\n      result: trap\n      post_state: \"01\"\n",
+        ),
+    );
+    let report = run(&test, &TestOptions::default()).unwrap();
+    assert_eq!(report.blocks[1].outcome, Some(BlockOutcome::Trap));
+    let (index, error) = stopped_at(report);
+    assert_eq!(index, 1);
+    assert!(error.starts_with("Invalid input"));
+}
+
+#[test]
+fn mismatches_are_reported() {
+    let test = write_test(
+        "mismatches",
+        &FAILING_BLOCK
+            .replace(
+                "    data: \"\"\n",
+                "    data: \"\"\n    expect:\n      result: success\n",
+            )
+            .replace(
+                "    - \"0202020202020202020202020202020202020202020202020202020202020202\"",
+                "    - \"0303030303030303030303030303030303030303030303030303030303030303\"",
+            ),
+    );
+    let report = run(&test, &TestOptions::default()).unwrap();
+    assert!(!report.passed());
+    let failed: Vec<&str> = report
+        .checks
+        .iter()
+        .filter(|check| !check.passed)
+        .map(|check| check.name.as_str())
+        .collect();
+    assert_eq!(failed, vec!["block 1", "state"]);
+    assert_eq!(report.blocks.len(), 3);
+
+    let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["blocks"][1]["outcome"], "trap");
+    assert_eq!(json["checks"][0]["passed"], false);
+    assert_eq!(json["checks"][1]["name"], "deposit receipts");
+}
+
+fn deposit(byte: u8, amount: u64) -> Deposit {
+    Deposit {
+        pubkey: BLSPubKey([byte; 48]),
+        amount,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn deposits_are_compared_in_order() {
+    let expected = vec![deposit(1, 32), deposit(2, 32)];
+    assert!(check_deposits(&expected, &expected, false).is_empty());
+
+    let reversed = vec![deposit(2, 32), deposit(1, 32)];
+    assert_eq!(check_deposits(&expected, &reversed, false).len(), 2);
+    assert!(check_deposits(&expected, &reversed, true).is_empty());
+
+    let changed = vec![deposit(1, 32), deposit(2, 16)];
+    assert_eq!(
+        check_deposits(&expected, &changed, false),
+        vec!["deposit 1: amount expected 32, got 16"]
+    );
+}
+
+#[test]
+fn deposit_counts_must_match() {
+    let expected = vec![deposit(1, 32)];
+    let duplicated = vec![deposit(1, 32), deposit(1, 32)];
+    for unordered in &[false, true] {
+        let mismatches = check_deposits(&expected, &duplicated, *unordered);
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0], "expected 1 deposits, got 2");
+    }
+    let missing = &check_deposits(&duplicated, &expected, true)[1];
+    assert_eq!(missing, &format!("missing deposit {:?}", deposit(1, 32)));
+    assert!(missing.contains(&format!("withdrawal_credentials: {},", "00".repeat(32))));
+}
+
+#[test]
+fn batches_of_tests() {
+    let passing = write_test(
+        "batch-passing",
+        &format!("tolerate_failures: true\n{}", FAILING_BLOCK),
+    );
+    let failing = write_test(
+        "batch-failing",
+        &FAILING_BLOCK.replace("    - \"02", "    - \"03"),
+    );
+    let dir = std::env::temp_dir().join(format!("scout-batch-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    fs::copy(&passing, dir.join("a.yaml")).unwrap();
+    fs::copy(&passing, dir.join("nested").join("b.yml")).unwrap();
+    fs::copy(&failing, dir.join("c.yaml")).unwrap();
+    fs::write(dir.join("notes.txt"), "").unwrap();
+    fs::write(dir.join("d.yaml"), "not a test").unwrap();
+    let (test_file, _) = load_test_file(passing.to_str().unwrap()).unwrap();
+    let json = TestFormat::Json.serialize(&test_file).unwrap();
+    fs::write(dir.join("nested").join("e.json"), json).unwrap();
+    fs::write(dir.join("report.json"), r#"{"tests": []}"#).unwrap();
+    fs::write(dir.join("broken.json"), "{").unwrap();
+
+    let files = collect_tests(&[dir.to_str().unwrap().to_string()]).unwrap();
+    let names: Vec<_> = files
+        .iter()
+        .map(|file| file.strip_prefix(&dir).unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "a.yaml",
+            "c.yaml",
+            "d.yaml",
+            "nested/b.yml",
+            "nested/e.json"
+        ]
+    );
+
+    let options = TestOptions {
+        tolerate_failures: true,
+        ..Default::default()
+    };
+    for parallel in &[false, true] {
+        let summary = run_tests(&files, &options, *parallel);
+        assert_eq!(summary.tests[3].file, files[3].to_str().unwrap());
+        assert_eq!(
+            (summary.passed(), summary.failed(), summary.errors()),
+            (3, 1, 1)
+        );
+    }
+
+    let pattern = format!("{}/*.yaml", dir.to_str().unwrap());
+    assert_eq!(collect_tests(&[pattern]).unwrap().len(), 3);
+    let missing = format!("{}/*.toml", dir.to_str().unwrap());
+    assert!(matches!(
+        collect_tests(&[missing]),
+        Err(ScoutError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn json_test_files() {
+    let yaml = write_test(
+        "json",
+        &format!("tolerate_failures: true\n{}", FAILING_BLOCK),
+    );
+    let (test_file, format) = load_test_file(yaml.to_str().unwrap()).unwrap();
+    assert_eq!(format, TestFormat::Yaml);
+    let json = TestFormat::Json.serialize(&test_file).unwrap();
+    assert_eq!(TestFormat::Json.parse(&json).unwrap(), test_file);
+
+    let dir = yaml.parent().unwrap();
+    fs::write(dir.join("test.json"), &json).unwrap();
+    fs::write(dir.join("test"), &json).unwrap();
+    for name in &["test.json", "test"] {
+        let path = dir.join(name);
+        let (_, format) = load_test_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(format, TestFormat::Json);
+        assert!(run(&path, &TestOptions::default()).unwrap().passed());
+    }
+
+    fs::write(dir.join("broken.json"), "{").unwrap();
+    assert!(matches!(
+        run(&dir.join("broken.json"), &TestOptions::default()),
+        Err(ScoutError::Json(_))
+    ));
+}
+
+#[test]
+fn fill_results() {
+    let test = write_test(
+        "fill",
+        &format!(
+            "tolerate_failures: true\n{}",
+            FAILING_BLOCK.replace("    - \"02", "    - \"03")
+        ),
+    );
+    assert!(!run(&test, &TestOptions::default()).unwrap().passed());
+
+    let (test_file, format) = fill_test(test.to_str().unwrap(), &TestOptions::default()).unwrap();
+    assert_eq!(format, TestFormat::Yaml);
+    assert_eq!(
+        test_file.shard_post_state.exec_env_states,
+        vec!["02".repeat(32)]
+    );
+    assert_eq!(test_file.shard_post_state.exec_env_storage, None);
+    assert!(test_file.deposit_receipts.is_empty());
+    let expect = test_file.shard_blocks[1].expect.as_ref().unwrap();
+    assert_eq!(expect.result, BlockOutcome::Trap);
+    assert_eq!(expect.post_state, Some("01".repeat(32)));
+    assert!(test_file.shard_blocks[2]
+        .expect
+        .as_ref()
+        .unwrap()
+        .ticks_used
+        .is_some());
+
+    let filled = test.with_extension("json");
+    fs::write(&filled, TestFormat::Json.serialize(&test_file).unwrap()).unwrap();
+    assert!(run(&filled, &TestOptions::default()).unwrap().passed());
+}
+
+#[test]
+fn fill_aborts_on_failing_blocks() {
+    let test = write_test("fill-failing", FAILING_BLOCK);
+    assert!(matches!(
+        fill_test(test.to_str().unwrap(), &TestOptions::default()),
+        Err(ScoutError::Trap(_))
+    ));
+}
+
+#[test]
+fn paths_are_relative_to_the_test_file() {
+    let test = write_test(
+        "relative-paths",
+        &format!("tolerate_failures: true\n{}", FAILING_BLOCK),
+    );
+    let dir = test.parent().unwrap();
+    let content = fs::read_to_string(&test)
+        .unwrap()
+        .replace(dir.to_str().unwrap(), ".");
+    fs::write(&test, content).unwrap();
+    assert!(run(&test, &TestOptions::default()).unwrap().passed());
+
+    let options = TestOptions {
+        base_dir: Some(std::env::temp_dir()),
+        ..Default::default()
+    };
+    match run(&test, &options) {
+        Err(ScoutError::InvalidInput(message)) => {
+            assert!(message.contains("execution script 0"));
+            assert!(message.contains("save_block.wasm"));
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    let options = TestOptions {
+        base_dir: Some(dir.to_path_buf()),
+        ..Default::default()
+    };
+    let moved = std::env::temp_dir().join(format!("scout-moved-{}.yaml", std::process::id()));
+    fs::copy(&test, &moved).unwrap();
+    assert!(run(&moved, &options).unwrap().passed());
+}
+
+const INLINE_SCRIPTS: &str = r#"
+beacon_state:
+  execution_scripts:
+    - wat: |
+        (module
+          (import "lib" "answer" (func $answer (result i32)))
+          (import "eth2" "savePostStateRoot" (func $save (param i32)))
+          (memory (export "memory") 1)
+          (func (export "main")
+            (i32.store8 (i32.const 0) (call $answer))
+            (call $save (i32.const 0))))
+    - hex: HEX
+    - base64: BASE64
+    - file: SCRIPT
+  libraries:
+    - name: lib
+      wat: '(module (func (export "answer") (result i32) (i32.const 42)))'
+shard_pre_state:
+  exec_env_states:
+    - "0000000000000000000000000000000000000000000000000000000000000000"
+    - "0000000000000000000000000000000000000000000000000000000000000000"
+    - "0000000000000000000000000000000000000000000000000000000000000000"
+    - "0000000000000000000000000000000000000000000000000000000000000000"
+shard_blocks:
+  - env: 0
+    data: ""
+  - env: 1
+    data: "0101010101010101010101010101010101010101010101010101010101010101"
+  - env: 2
+    data: "0202020202020202020202020202020202020202020202020202020202020202"
+  - env: 3
+    data: "0303030303030303030303030303030303030303030303030303030303030303"
+shard_post_state:
+  exec_env_states:
+    - "2a00000000000000000000000000000000000000000000000000000000000000"
+    - "0101010101010101010101010101010101010101010101010101010101010101"
+    - "0202020202020202020202020202020202020202020202020202020202020202"
+    - "0303030303030303030303030303030303030303030303030303030303030303"
+deposit_receipts: []
+"#;
+
+#[test]
+fn inline_scripts() {
+    let code = wat::parse_str(SAVE_BLOCK).unwrap();
+    let content = INLINE_SCRIPTS
+        .replace("HEX", &code.to_hex())
+        .replace("BASE64", &base64::encode(&code));
+    let test = write_test("inline-scripts", &content);
+    assert!(run(&test, &TestOptions::default()).unwrap().passed());
+
+    let test = write_test(
+        "invalid-inline-script",
+        &content.replace("(i32.const 42)", "(i32.const)"),
+    );
+    match run(&test, &TestOptions::default()) {
+        Err(ScoutError::InvalidInput(message)) => assert!(message.contains("library 0 (lib)")),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn ssz_block_data() {
+    let fields = vec![
+        TestSszField::Bool(true),
+        TestSszField::Uint16(0x0102),
+        TestSszField::List("aabb".to_string()),
+        TestSszField::Uint32(3),
+        TestSszField::List("cc".to_string()),
+        TestSszField::Vector("dd".to_string()),
+    ];
+    assert_eq!(
+        encode_ssz(&fields).unwrap().to_hex(),
+        "010201100000000300000012000000ddaabbcc"
+    );
+
+    let mut expected = vec![0u8; 32];
+    expected[0] = 0x02;
+    expected[1] = 0x01;
+    for value in &["258", "0x0102"] {
+        let fields = vec![TestSszField::Uint256(value.to_string())];
+        assert_eq!(encode_ssz(&fields).unwrap(), expected);
+    }
+    let too_large = format!("0x01{}", "00".repeat(32));
+    for value in &["x", too_large.as_str()] {
+        assert!(matches!(
+            encode_ssz(&[TestSszField::Uint256(value.to_string())]),
+            Err(ScoutError::InvalidInput(_))
+        ));
+    }
+    let largest = format!("0x{}", "ff".repeat(32));
+    assert_eq!(
+        encode_ssz(&[TestSszField::Uint256(largest)]).unwrap(),
+        vec![0xff; 32]
+    );
+}
+
+#[test]
+fn block_data_from_files_and_ssz() {
+    let test = write_test(
+        "block-data",
+        &FAILING_BLOCK
+            .replace(
+                "    data: \"0101010101010101010101010101010101010101010101010101010101010101\"",
+                "    data:\n      file: block.bin",
+            )
+            .replace(
+                "    data: \"0202020202020202020202020202020202020202020202020202020202020202\"",
+                "    data:\n      ssz:\n        - uint64: 2\n        - vector: \"020202020202020202020202020202020202020202020202\"",
+            )
+            .replace(
+                "    - \"0202020202020202020202020202020202020202020202020202020202020202\"",
+                "    - \"0200000000000000020202020202020202020202020202020202020202020202\"",
+            ),
+    );
+    fs::write(test.parent().unwrap().join("block.bin"), [1u8; 32]).unwrap();
+    let options = TestOptions {
+        tolerate_failures: true,
+        ..Default::default()
+    };
+    let report = run(&test, &options).unwrap();
+    assert!(report.passed());
+    assert_eq!(report.blocks[0].post_state, Some("01".repeat(32)));
+}
+
+#[test]
+fn tick_budget_precedence() {
+    let outcomes = |header: &str, ticks: u32| -> Vec<BlockOutcome> {
+        let test = write_test(
+            "tick-budgets",
+            &format!(
+                "tolerate_failures: true\n{}{}",
+                header,
+                FAILING_BLOCK.replace("    data: \"02", "    ticks: 1\n    data: \"02")
+            ),
+        );
+        let options = TestOptions {
+            ticks,
+            ..Default::default()
+        };
+        run(&test, &options)
+            .unwrap()
+            .blocks
+            .iter()
+            .map(|block| block.outcome.unwrap())
+            .collect()
+    };
+    use BlockOutcome::*;
+    // The last block has a budget of its own, which is always too small.
+    assert_eq!(outcomes("", 1000), vec![Success, Trap, OutOfTicks]);
+    assert_eq!(outcomes("", 1), vec![OutOfTicks, OutOfTicks, OutOfTicks]);
+    assert_eq!(
+        outcomes("ticks: 1000\n", 1),
+        vec![Success, Trap, OutOfTicks]
+    );
+    assert_eq!(
+        outcomes("ticks: 1000\nenv_ticks:\n  0: 1\n", 1000),
+        vec![OutOfTicks, OutOfTicks, OutOfTicks]
+    );
+    assert_eq!(
+        outcomes("ticks: 1\nenv_ticks:\n  0: 1000\n", 1),
+        vec![Success, Trap, OutOfTicks]
+    );
+}
+
+#[test]
+fn no_metering_overrides_tick_costs() {
+    let test = write_test(
+        "no-metering",
+        &format!(
+            "tolerate_failures: true\ntick_costs:\n  regular: 1\n{}",
+            FAILING_BLOCK
+        ),
+    );
+    let ticks_used = |options: &TestOptions| run(&test, options).unwrap().blocks[0].ticks_used;
+    assert!(ticks_used(&TestOptions::default()).unwrap() > 0);
+    let options = TestOptions {
+        tick_costs: TickCosts::disabled(),
+        ..Default::default()
+    };
+    assert_eq!(ticks_used(&options), Some(0));
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use common::run_wat;
+use phase2_scout::types::Bytes32;
+use phase2_scout::{execute_code, ExecutionResult, Library, ScoutError, ShardBlockBody, TickCosts};
+
+fn run_use_ticks(ticks: u32) -> Result<ExecutionResult, ScoutError> {
+    run_wat(
+        r#"(module
+            (import "eth2" "useTicks" (func $use (param i32)))
+            (memory (export "memory") 1)
+            (func (export "main")
+                (call $use (i32.const 100))
+                (call $use (i32.const 100))))"#,
+        ticks,
+        &TickCosts::disabled(),
+    )
+}
+
+/// Adds 1 to a local in a loop of 10 iterations.
+const COUNTING_LOOP: &str = r#"(module
+    (memory (export "memory") 1)
+    (func (export "main")
+        (local $i i32)
+        (loop $next
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br_if $next (i32.lt_u (local.get $i) (i32.const 10))))))"#;
+
+#[test]
+fn reports_used_ticks() {
+    let result = run_use_ticks(1000).unwrap();
+    assert_eq!(result.ticks_used, 200);
+    assert_eq!(result.ticks_left, 800);
+}
+
+#[test]
+fn exact_budget() {
+    let result = run_use_ticks(200).unwrap();
+    assert_eq!(result.ticks_used, 200);
+    assert_eq!(result.ticks_left, 0);
+}
+
+#[test]
+fn insufficient_budget() {
+    assert!(matches!(run_use_ticks(150), Err(ScoutError::OutOfTicks)));
+}
+
+#[test]
+fn metering_charges_instructions() {
+    let result = run_wat(COUNTING_LOOP, 1000, &TickCosts::default()).unwrap();
+    assert!(result.ticks_used > 0);
+
+    let unmetered = run_wat(COUNTING_LOOP, 1000, &TickCosts::disabled()).unwrap();
+    assert_eq!(unmetered.ticks_used, 0);
+}
+
+#[test]
+fn metering_adds_to_use_ticks() {
+    let result = run_use_ticks(1000).unwrap();
+    let metered = run_wat(
+        r#"(module
+            (import "eth2" "useTicks" (func $use (param i32)))
+            (memory (export "memory") 1)
+            (func (export "main")
+                (call $use (i32.const 100))
+                (call $use (i32.const 100))))"#,
+        1000,
+        &TickCosts::default(),
+    )
+    .unwrap();
+    assert!(metered.ticks_used > result.ticks_used);
+}
+
+#[test]
+fn instruction_costs_are_configurable() {
+    let cheap = run_wat(COUNTING_LOOP, 1000, &TickCosts::default()).unwrap();
+    let mut costs = TickCosts::default();
+    costs.instructions.insert("add".to_string(), 11);
+    let expensive = run_wat(COUNTING_LOOP, 1000, &costs).unwrap();
+    // One add per iteration, each costing 10 more.
+    assert_eq!(expensive.ticks_used, cheap.ticks_used + 10 * 10);
+}
+
+#[test]
+fn unknown_instruction_class() {
+    let mut costs = TickCosts::default();
+    costs.instructions.insert("teleport".to_string(), 1);
+    assert!(matches!(
+        run_wat(COUNTING_LOOP, 1000, &costs),
+        Err(ScoutError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn infinite_loop_runs_out_of_ticks() {
+    let ret = run_wat(
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "main") (loop $forever (br $forever))))"#,
+        100_000,
+        &TickCosts::default(),
+    );
+    assert!(matches!(ret, Err(ScoutError::OutOfTicks)));
+}
+
+#[test]
+fn memory_growth_is_charged() {
+    let source = r#"(module
+        (memory (export "memory") 1)
+        (func (export "main") (drop (memory.grow (i32.const 3)))))"#;
+    let free = run_wat(source, 1000, &TickCosts::default()).unwrap();
+    let costs = TickCosts {
+        grow_memory: 100,
+        ..Default::default()
+    };
+    let charged = run_wat(source, 1000, &costs).unwrap();
+    assert_eq!(charged.ticks_used, free.ticks_used + 300);
+}
+
+#[test]
+fn metered_start_function() {
+    let ret = run_wat(
+        r#"(module
+            (memory (export "memory") 1)
+            (func $start (loop $forever (br $forever)))
+            (start $start)
+            (func (export "main")))"#,
+        1000,
+        &TickCosts::default(),
+    );
+    assert!(matches!(ret, Err(ScoutError::OutOfTicks)));
+}
+
+fn run_with_library(library: &str, function: &str) -> Result<ExecutionResult, ScoutError> {
+    let source = format!(
+        r#"(module
+            (import "lib" "{}" (func $f))
+            (memory (export "memory") 1)
+            (func (export "main") (call $f)))"#,
+        function
+    );
+    let library = Library {
+        name: "lib".to_string(),
+        code: wat::parse_str(library).expect("valid wat"),
+    };
+    execute_code(
+        &wat::parse_str(&source).expect("valid wat"),
+        &[library],
+        &Bytes32::default(),
+        &ShardBlockBody::default(),
+        1000,
+        &TickCosts::default(),
+    )
+}
+
+#[test]
+fn libraries_are_metered() {
+    let library = r#"(module
+        (func (export "spin") (loop $l (br $l)))
+        (func (export "count")
+            (local $i i32)
+            (loop $next
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br_if $next (i32.lt_u (local.get $i) (i32.const 10))))))"#;
+    assert!(matches!(
+        run_with_library(library, "spin"),
+        Err(ScoutError::OutOfTicks)
+    ));
+    let result = run_with_library(library, "count").unwrap();
+    assert!(result.ticks_used > 10);
+
+    let looping_start = r#"(module
+        (func $start (loop $l (br $l)))
+        (start $start)
+        (func (export "f")))"#;
+    assert!(matches!(
+        run_with_library(looping_start, "f"),
+        Err(ScoutError::OutOfTicks)
+    ));
+}